@@ -198,7 +198,8 @@ pub struct Connected {
     pub players: Vec<NetworkPlayer>,
     pub missing_locations: Vec<i64>,
     pub checked_locations: Vec<i64>,
-    pub slot_data: Option<SlotData>,
+    // Left unparsed so clients can keep it exactly as sent, SlotData reads the fields the launcher uses
+    pub slot_data: Option<serde_json::Value>,
     pub slot_info: HashMap<String, NetworkSlot>,
     pub hint_points: i64
 }
@@ -232,7 +233,7 @@ pub struct RoomUpdate {
     pub players: Option<Vec<NetworkPlayer>>,
     pub missing_locations: Option<Vec<i64>>,
     pub checked_locations: Option<Vec<i64>>,
-    pub slot_data: Option<SlotData>,
+    pub slot_data: Option<serde_json::Value>,
     pub slot_info: Option<HashMap<String, NetworkSlot>>,
     pub hint_points: Option<i64>
 }
@@ -360,7 +361,7 @@ pub enum HintStatus {
     HintFound = 40
 }
 
-#[derive(Clone, Serialize_repr, Deserialize_repr, Debug)]
+#[derive(Clone, Copy, PartialEq, Serialize_repr, Deserialize_repr, Debug)]
 #[repr(u16)]
 pub enum ClientStatus {
     ClientUnknown = 0,
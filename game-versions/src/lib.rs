@@ -0,0 +1,144 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+
+const VERSIONS_FILENAME: &str = "lamulana-versions.toml";
+const EMBEDDED_VERSIONS: &str = include_str!("../../versions.toml");
+
+// Every symbol the DLL reads or calls. Patch targets are only required for the versions that define patches
+pub const REQUIRED_SYMBOLS: [&str; 24] = [
+    "set_se",
+    "default_final",
+    "item_get_area_init",
+    "item_symbol_init",
+    "item_symbol_back",
+    "set_view_event_ns",
+    "popup_dialog_init",
+    "popup_dialog_draw",
+    "game_loop",
+    "se",
+    "script_header_pointer",
+    "inventory_words",
+    "system_flags",
+    "global_flags",
+    "current_scene",
+    "current_screen",
+    "current_field",
+    "option_sdata_num",
+    "option_sdata",
+    "option_pos_cx",
+    "option_pos_cy",
+    "game_process",
+    "lemeza_pointer",
+    "game_init"
+];
+
+// PrintJSON types the DLL shows in its message feed unless message_feed_types is set, kept here so the launcher writes the same defaults
+pub const DEFAULT_MESSAGE_FEED_TYPES: [&str; 8] = ["ItemSend", "Hint", "Chat", "ServerChat", "Goal", "Release", "Collect", "Countdown"];
+
+// The launcher uses the digests and save location, the DLL the symbols and patches
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct GameVersion {
+    pub version: String,
+    pub exe_digest: String,
+    pub rcd_digest: String,
+    pub dat_digest: String,
+    pub effects_digest: String,
+    pub save_path: String,
+    #[serde(default)]
+    pub steam: bool,
+    #[serde(default)]
+    pub symbols: HashMap<String, usize>,
+    #[serde(default)]
+    pub patches: HashMap<String, Vec<u8>>
+}
+
+#[derive(Deserialize)]
+struct VersionsFile {
+    version: Vec<GameVersion>
+}
+
+// Prefers a versions file next to the executable, so new game versions can be supported without rebuilding the DLL or the launcher
+pub fn load_versions() -> Result<Vec<GameVersion>, String> {
+    let file_contents = match fs::read_to_string(VERSIONS_FILENAME) {
+        Ok(file_contents) => file_contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => EMBEDDED_VERSIONS.to_string(),
+        Err(e) => return Err(format!("Error reading {}: {}", VERSIONS_FILENAME, e))
+    };
+    parse_versions(&file_contents)
+}
+
+// Every version has to define every required symbol, otherwise the DLL would refuse to attach after the launcher accepted the install
+pub fn parse_versions(file_contents: &str) -> Result<Vec<GameVersion>, String> {
+    let versions_file = toml::from_str::<VersionsFile>(file_contents).map_err(|e| format!("Error parsing versions: {}", e))?;
+    validate_versions(&versions_file.version)?;
+    Ok(versions_file.version)
+}
+
+pub fn missing_symbols(game_version: &GameVersion) -> Vec<String> {
+    REQUIRED_SYMBOLS.iter()
+        .map(|symbol| symbol.to_string())
+        .chain(game_version.patches.keys().cloned())
+        .filter(|symbol| !game_version.symbols.contains_key(symbol))
+        .collect::<Vec<String>>()
+}
+
+fn validate_versions(versions: &[GameVersion]) -> Result<(), String> {
+    let invalid_versions = versions.iter().filter_map(|game_version| {
+        let missing_symbols = missing_symbols(game_version);
+        if missing_symbols.is_empty() {
+            None
+        } else {
+            Some(format!("Version {} is missing symbols: {}", game_version.version, missing_symbols.join(", ")))
+        }
+    }).collect::<Vec<String>>();
+
+    if invalid_versions.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid_versions.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version_with_symbols(symbols: &[&str]) -> String {
+        let symbols = symbols.iter().enumerate().map(|(index, symbol)| format!("{} = {}", symbol, 0x401000 + index)).collect::<Vec<String>>();
+        format!(
+            "[[version]]\nversion = \"1.0.0.1\"\nexe_digest = \"\"\nrcd_digest = \"\"\ndat_digest = \"\"\neffects_digest = \"\"\nsave_path = \"install\"\n\n[version.symbols]\n{}\n",
+            symbols.join("\n")
+        )
+    }
+
+    #[test]
+    fn embedded_versions_define_every_required_symbol() {
+        let versions = parse_versions(EMBEDDED_VERSIONS).unwrap();
+        assert!(!versions.is_empty());
+        for game_version in versions {
+            assert_eq!(missing_symbols(&game_version), Vec::<String>::new(), "Version {}", game_version.version);
+        }
+    }
+
+    #[test]
+    fn versions_missing_a_required_symbol_are_rejected() {
+        let error = parse_versions(&version_with_symbols(&REQUIRED_SYMBOLS[1..])).err().unwrap();
+        assert_eq!(error, "Version 1.0.0.1 is missing symbols: set_se");
+    }
+
+    #[test]
+    fn patches_without_a_symbol_are_rejected() {
+        let file_contents = format!("{}\n[version.patches]\nshop_orb_patch = [0x74, 0x27]\n", version_with_symbols(&REQUIRED_SYMBOLS));
+        let error = parse_versions(&file_contents).err().unwrap();
+        assert_eq!(error, "Version 1.0.0.1 is missing symbols: shop_orb_patch");
+    }
+
+    #[test]
+    fn versions_with_every_required_symbol_are_accepted() {
+        let versions = parse_versions(&version_with_symbols(&REQUIRED_SYMBOLS)).unwrap();
+        assert_eq!(versions[0].symbols.len(), REQUIRED_SYMBOLS.len());
+        assert!(!versions[0].steam);
+    }
+}
@@ -1,9 +1,14 @@
-use log::debug;
+use log::{debug, warn};
 use serde::{Serialize, Deserialize};
 use slint::SharedString;
+use toml::Value;
 
-use crate::consts::{AP_DATA_PATH, AP_PATH};
+use crate::consts::{AP_DATA_PATH, AP_PATH, APP_CONFIG_FILE_NAME};
+use crate::file_gen::app_config::Settings;
+use crate::file_gen::generator::{self, RegenerationReport};
 use crate::file_utils;
+use crate::rotation::{self, Recovery};
+use crate::snapshots;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct LaMulanaConfig {
@@ -37,27 +42,42 @@ pub struct APData {
 
 impl APData {
     pub fn new(lm_config: LaMulanaConfig) -> Result<APData, String> {
-        let ap_data_found = file_utils::path_exists(&AP_DATA_PATH, false)?;
-
-        if ap_data_found {
-            let serialized_ap_data = file_utils::read_file_as_string(&AP_DATA_PATH)?;
-            match serde_json::from_str::<APData>(&serialized_ap_data) {
-                Ok(mut ap_data) => {
-                    ap_data.config = lm_config;
-                    return Ok(ap_data);
-                },
-                Err(e) => {
-                    debug!("Error {} while attempting to deserialize AP Data, regenerating", e);
-                }
-            }
+        // The game still runs on the seed's own settings without it
+        if let Err(e) = Settings::write_default() {
+            warn!("Could not write default settings: {}", e);
         }
 
-        let ap_data = APData { config: lm_config, games: Vec::new(), active_game: None };
-        ap_data.serialize_data()?;
+        let mut ap_data = match APData::read()? {
+            Some(mut ap_data) => {
+                ap_data.config = lm_config;
+                ap_data
+            },
+            None => {
+                let ap_data = APData { config: lm_config, games: Vec::new(), active_game: None };
+                ap_data.serialize_data()?;
+                ap_data
+            }
+        };
+        ap_data.recover_rotation();
 
         Ok(ap_data)
     }
 
+    fn read() -> Result<Option<APData>, String> {
+        if !file_utils::path_exists(&AP_DATA_PATH, false)? {
+            return Ok(None);
+        }
+
+        let serialized_ap_data = file_utils::read_file_as_string(&AP_DATA_PATH)?;
+        match serde_json::from_str::<APData>(&serialized_ap_data) {
+            Ok(ap_data) => Ok(Some(ap_data)),
+            Err(e) => {
+                debug!("Error {} while attempting to deserialize AP Data, regenerating", e);
+                Ok(None)
+            }
+        }
+    }
+
     pub fn seed_selected(&self) -> bool {
         self.active_game.is_some()
     }
@@ -77,6 +97,18 @@ impl APData {
         self.games.iter().map(|game| game.seed.clone().into()).collect()
     }
 
+    pub fn game(&self, seed: &str) -> Option<&Game> {
+        self.games.iter().find(|game| game.seed == seed)
+    }
+
+    // A seed's saves live in the game's save directory while it is loaded
+    pub fn seed_save_path(&self, seed: &str) -> String {
+        match &self.active_game {
+            Some(active_game) if active_game.seed == seed => self.config.save_path.clone(),
+            _ => format!("{}{}/save/", AP_PATH, seed)
+        }
+    }
+
     pub fn add_new_game(&mut self, game: Game) -> Result<(), String> {
         self.rotate_files(game.seed.clone())?;
         self.active_game = Some(game.clone());
@@ -85,6 +117,15 @@ impl APData {
         Ok(())
     }
 
+    // Imported seeds are only listed, loading them stays a separate step
+    pub fn import_game(&mut self, game: Game) -> Result<(), String> {
+        if self.game(&game.seed).is_some() {
+            return Err(format!("Seed {} already exists.", game.seed));
+        }
+        self.games.push(game);
+        self.serialize_data()
+    }
+
     pub fn load_game(&mut self, seed: String) -> Result<(), String> {
         match self.games.iter().find(|&game| game.seed == seed) {
             Some(game) => {
@@ -117,12 +158,74 @@ impl APData {
         self.serialize_data()
     }
 
+    // Room restarts change the port, so the connection can be changed without touching the generated files
+    pub fn update_connection(&mut self, seed: String, server_url: String, password: String, player_name: String) -> Result<(), String> {
+        let Some(game) = self.games.iter_mut().find(|game| game.seed == seed) else {
+            let error_message = format!("Couldn't find Seed {} while attempting to update its connection.", seed);
+            debug!("{}", error_message);
+            return Err(error_message);
+        };
+
+        let app_config_path = format!("{}{}/{}", AP_PATH, seed, APP_CONFIG_FILE_NAME);
+        update_app_config_connection(&app_config_path, &server_url, &password, game.you.id, &player_name)?;
+
+        game.server_url = server_url;
+        game.password = password;
+        game.you.name = player_name;
+        let game = game.clone();
+
+        if self.active_game.as_ref().is_some_and(|active_game| active_game.seed == seed) {
+            file_utils::copy_file(&app_config_path, &format!("./{}", APP_CONFIG_FILE_NAME))?;
+            self.active_game = Some(game);
+        }
+        self.serialize_data()
+    }
+
+    // The seed's save directory is left alone, an active seed gets its new files copied straight into the game
+    pub fn regenerate_game(&self, seed: String) -> Result<RegenerationReport, String> {
+        if !self.games.iter().any(|game| game.seed == seed) {
+            let error_message = format!("Couldn't find Seed {} while attempting to regenerate it.", seed);
+            debug!("{}", error_message);
+            return Err(error_message);
+        }
+
+        let report = generator::regenerate_files(seed.clone()).map_err(|e| {
+            debug!("Files failed to Regenerate: {:?}", e);
+            e.to_string()
+        })?;
+
+        if self.active_game.as_ref().is_some_and(|active_game| active_game.seed == seed) {
+            self.rotate_files(seed)?;
+        }
+        Ok(report)
+    }
+
     fn rotate_files(&self, seed: String) -> Result<(), String> {
-        if let Some(active_game) = &self.active_game {
-            let save_destination = format!("{}{}/save/", AP_PATH, active_game.seed);
-            file_utils::move_saves(self.config.save_path.clone(), save_destination)?;
+        let previous_seed = self.active_game.as_ref().map(|active_game| active_game.seed.clone());
+        if let Some(previous_seed) = &previous_seed {
+            // A failed snapshot shouldn't keep the player from switching seeds
+            if let Err(e) = snapshots::take_snapshot(self, previous_seed, "swap") {
+                debug!("Failed to snapshot saves for {}: {}", previous_seed, e);
+            }
+        }
+        rotation::rotate(previous_seed, seed, self.config.save_path.clone())
+    }
+
+    // A rotation that rolled forward left the install on its seed, so the active game has to follow
+    // A failed recovery leaves the journal in place to be retried on the next start, the launcher still opens meanwhile
+    fn recover_rotation(&mut self) {
+        match rotation::recover() {
+            Ok(Some(Recovery::RolledForward(seed))) => {
+                self.active_game = self.game(&seed).cloned();
+                if let Err(e) = self.serialize_data() {
+                    warn!("Could not record the recovered seed switch to {}: {}", seed, e);
+                }
+            },
+            Ok(_) => {},
+            Err(e) => {
+                warn!("Could not recover the incomplete seed switch, it will be retried on the next start: {}", e);
+            }
         }
-        file_utils::update_game_files(seed, self.config.save_path.clone())
     }
 
     fn serialize_data(&self) -> Result<(), String> {
@@ -132,3 +235,30 @@ impl APData {
         file_utils::write_file(&AP_DATA_PATH, &serialized_ap_data)
     }
 }
+
+// Edited as plain toml, so configs from older launchers keep any fields this one doesn't know about
+fn update_app_config_connection(app_config_path: &str, server_url: &str, password: &str, player_id: i64, player_name: &str) -> Result<(), String> {
+    let serialized_app_config = file_utils::read_file_as_string(app_config_path)?;
+    let mut app_config = toml::from_str::<Value>(&serialized_app_config).map_err(|e| {
+        format!("Error {} while attempting to deserialize {}", e, app_config_path)
+    })?;
+    let app_config_table = app_config.as_table_mut().ok_or_else(|| format!("{} is not a toml table", app_config_path))?;
+
+    app_config_table.insert("server_url".to_string(), Value::String(server_url.to_string()));
+    app_config_table.insert("password".to_string(), Value::String(password.to_string()));
+
+    // The DLL connects with the name it finds for its own id in the players list
+    if let Some(players) = app_config_table.get_mut("players").and_then(|players| players.as_array_mut()) {
+        let local_players = players.iter_mut().filter_map(|player| player.as_table_mut()).filter(|player| {
+            player.get("id").and_then(|id| id.as_integer()) == Some(player_id)
+        });
+        for player in local_players {
+            player.insert("name".to_string(), Value::String(player_name.to_string()));
+        }
+    }
+
+    let serialized_app_config = toml::to_string(&app_config).map_err(|e| {
+        format!("Error {} while attempting to serialize {}", e, app_config_path)
+    })?;
+    file_utils::write_file(app_config_path, serialized_app_config)
+}
@@ -0,0 +1,231 @@
+use log::debug;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use toml::Value;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use zip::write::SimpleFileOptions;
+
+use crate::ap_data::{APData, Game};
+use crate::consts::{AP_PATH, APP_CONFIG_FILE_NAME, DLL_LOG_PATH, DLL_NAME, EXPORTS_PATH, LAUNCHER_LOG_PATH};
+use crate::file_utils;
+
+const BUNDLE_INFO_FILE_NAME: &str = "bundle.json";
+const SEED_DIR: &str = "seed/";
+const LOGS_DIR: &str = "logs/";
+// Only the end of each log is bundled, which covers the session being reported
+const LOG_TAIL_BYTES: usize = 512 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BundleInfo {
+    pub seed: String,
+    pub launcher_version: String,
+    pub game_version: String,
+    pub dll_digest: Option<String>,
+    pub game: Game,
+    pub password_redacted: bool
+}
+
+pub struct ImportedBundle {
+    pub seed: String,
+    pub password_redacted: bool
+}
+
+// Passwords are stripped from the ap_data entry, every config and the logs, so bundles can be shared publicly
+pub fn export_seed(ap_data: &APData, seed: &str, bundle_path: Option<String>) -> Result<String, String> {
+    let game = ap_data.game(seed).ok_or_else(|| format!("Couldn't find Seed {} while attempting to export it.", seed))?;
+    let seed_path = format!("{}{}/", AP_PATH, seed);
+
+    let bundle_path = match bundle_path {
+        Some(bundle_path) => bundle_path,
+        None => {
+            file_utils::create_dir(&EXPORTS_PATH)?;
+            format!("{}{}.zip", *EXPORTS_PATH, seed)
+        }
+    };
+
+    let bundle_info = BundleInfo {
+        seed: seed.to_string(),
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        game_version: ap_data.config.version.clone(),
+        dll_digest: file_utils::read_file(DLL_NAME).ok().map(|dll| hex::encode(Sha256::digest(dll)).to_uppercase()),
+        game: Game { password: "".to_string(), ..game.clone() },
+        password_redacted: !game.password.is_empty()
+    };
+    let bundle_info_bytes = serde_json::to_vec_pretty(&bundle_info).map_err(|e| {
+        format!("Error {} while attempting to serialize bundle info for {}", e, seed)
+    })?;
+
+    let bundle_file = File::create(&bundle_path).map_err(|e| {
+        format!("File system error {} attempting to create {}, please correct and try again.", e, bundle_path)
+    })?;
+    let mut bundle = ZipWriter::new(bundle_file);
+    let write_entry = |bundle: &mut ZipWriter<File>, entry_name: &str, contents: &[u8]| -> Result<(), String> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        bundle.start_file(entry_name, options).map_err(|e| {
+            format!("Error {} while attempting to add {} to {}", e, entry_name, bundle_path)
+        })?;
+        bundle.write_all(contents).map_err(|e| {
+            format!("Error {} while attempting to add {} to {}", e, entry_name, bundle_path)
+        })
+    };
+
+    write_entry(&mut bundle, BUNDLE_INFO_FILE_NAME, &bundle_info_bytes)?;
+
+    // Every password found is also scrubbed from the logs, a config kept by a regeneration can hold one the seed no longer uses
+    let mut passwords = vec![game.password.clone()];
+    for seed_file in file_utils::read_dir(&seed_path)?.filter_map(|seed_file| seed_file.ok()).filter(|seed_file| seed_file.path().is_file()) {
+        let file_name = seed_file.file_name().to_string_lossy().to_string();
+        let mut contents = file_utils::read_file(&seed_file.path().to_string_lossy())?;
+        if file_name.ends_with(".toml") {
+            let (redacted_contents, password) = redact_app_config(&file_name, &contents)?;
+            contents = redacted_contents;
+            passwords.extend(password);
+        }
+        write_entry(&mut bundle, &format!("{}{}", SEED_DIR, file_name), &contents)?;
+    }
+
+    let save_path = ap_data.seed_save_path(seed);
+    if file_utils::path_exists(&save_path, false)? {
+        for save_file in file_utils::read_dir(&save_path)?.filter_map(|save_file| save_file.ok()).filter(|save_file| save_file.path().is_file()) {
+            let file_name = save_file.file_name().to_string_lossy().to_string();
+            let contents = file_utils::read_file(&save_file.path().to_string_lossy())?;
+            write_entry(&mut bundle, &format!("{}save/{}", SEED_DIR, file_name), &contents)?;
+        }
+    }
+
+    for log_path in [LAUNCHER_LOG_PATH, DLL_LOG_PATH] {
+        if let Ok(log) = file_utils::read_file(log_path) {
+            let log_tail = redact_log(&log[log.len().saturating_sub(LOG_TAIL_BYTES)..], &passwords);
+            write_entry(&mut bundle, &format!("{}{}", LOGS_DIR, log_path), log_tail.as_bytes())?;
+        }
+    }
+
+    bundle.finish().map_err(|e| format!("Error {} while attempting to finish {}", e, bundle_path))?;
+    debug!("Exported Seed {} to {}", seed, bundle_path);
+    Ok(bundle_path)
+}
+
+// Recreates the seed under ap/ without loading it, logs in the bundle are left out
+pub fn import_seed(ap_data: &mut APData, bundle_path: &str) -> Result<ImportedBundle, String> {
+    let bundle_file = File::open(bundle_path).map_err(|e| {
+        format!("File system error {} attempting to read {}, please correct and try again.", e, bundle_path)
+    })?;
+    let mut bundle = ZipArchive::new(bundle_file).map_err(|e| format!("{} is not a seed bundle: {}", bundle_path, e))?;
+
+    let bundle_info = {
+        let mut bundle_info_entry = bundle.by_name(BUNDLE_INFO_FILE_NAME).map_err(|_| format!("{} is missing {}", bundle_path, BUNDLE_INFO_FILE_NAME))?;
+        let mut serialized_bundle_info = String::new();
+        bundle_info_entry.read_to_string(&mut serialized_bundle_info).map_err(|e| format!("Error {} while attempting to read {}", e, BUNDLE_INFO_FILE_NAME))?;
+        serde_json::from_str::<BundleInfo>(&serialized_bundle_info).map_err(|e| {
+            format!("Error {} while attempting to deserialize {}", e, BUNDLE_INFO_FILE_NAME)
+        })?
+    };
+
+    // The seed name becomes a directory, so it must be a single plain path component
+    let seed = bundle_info.seed.clone();
+    if seed.is_empty() || seed.contains(['/', '\\']) || [".", "..", "source"].contains(&seed.as_str()) {
+        return Err(format!("{} has an invalid seed name {}", bundle_path, seed));
+    }
+    if ap_data.game(&seed).is_some() {
+        return Err(format!("Seed {} already exists, delete it first to import it again", seed));
+    }
+
+    let seed_path = format!("{}{}/", AP_PATH, seed);
+    for index in 0..bundle.len() {
+        let mut entry = bundle.by_index(index).map_err(|e| format!("Error {} while attempting to read {}", e, bundle_path))?;
+        let Some(relative_path) = entry.enclosed_name().and_then(|entry_path| entry_path.strip_prefix(SEED_DIR).ok().map(|path| path.to_path_buf())) else {
+            continue;
+        };
+        if entry.is_dir() || relative_path.as_os_str().is_empty() {
+            continue;
+        }
+
+        let destination = Path::new(&seed_path).join(&relative_path);
+        if let Some(parent) = destination.parent() {
+            file_utils::create_dir(&parent.to_string_lossy())?;
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(|e| format!("Error {} while attempting to read {} from {}", e, entry.name(), bundle_path))?;
+        file_utils::write_file(&destination.to_string_lossy(), contents)?;
+    }
+
+    if !file_utils::path_exists(&format!("{}{}", seed_path, APP_CONFIG_FILE_NAME), false)? {
+        let _ = file_utils::delete_seed(seed.clone());
+        return Err(format!("{} has no generated files for Seed {}", bundle_path, seed));
+    }
+    file_utils::create_dir(&format!("{}save/", seed_path))?;
+
+    ap_data.import_game(bundle_info.game)?;
+    debug!("Imported Seed {} from {}", seed, bundle_path);
+    Ok(ImportedBundle { seed, password_redacted: bundle_info.password_redacted })
+}
+
+// Any config shaped toml file in the seed directory, the current config and the copy kept by a regeneration alike
+fn redact_app_config(file_name: &str, app_config: &[u8]) -> Result<(Vec<u8>, Option<String>), String> {
+    let serialized_app_config = String::from_utf8_lossy(app_config);
+    let mut app_config = toml::from_str::<Value>(&serialized_app_config).map_err(|e| {
+        format!("Error {} while attempting to deserialize {}", e, file_name)
+    })?;
+    let password = app_config.as_table_mut().and_then(|app_config_table| {
+        app_config_table.insert("password".to_string(), Value::String("".to_string()))
+    }).and_then(|password| password.as_str().map(|password| password.to_string()));
+    let redacted_app_config = toml::to_vec(&app_config).map_err(|e| format!("Error {} while attempting to serialize {}", e, file_name))?;
+    Ok((redacted_app_config, password))
+}
+
+fn redact_log(log: &[u8], passwords: &[String]) -> String {
+    passwords.iter().filter(|password| !password.is_empty()).fold(String::from_utf8_lossy(log).to_string(), |log, password| {
+        log.replace(password.as_str(), "<redacted>")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ap_data::{LaMulanaConfig, Player};
+    use crate::consts::PREVIOUS_APP_CONFIG_FILE_NAME;
+    use crate::file_gen::generator::keep_previous_app_config;
+
+    const PASSWORD: &str = "bundle-test-password";
+
+    #[test]
+    fn exported_bundle_leaves_out_every_password_after_a_regeneration() {
+        let seed = "bundle_redaction_test";
+        let seed_path = format!("{}{}/", AP_PATH, seed);
+        let bundle_path = format!("{}{}.zip", AP_PATH, seed);
+        let _ = std::fs::remove_dir_all(&seed_path);
+        file_utils::create_dir(&seed_path).unwrap();
+        let app_config = format!("server_url = \"localhost:38281\"\npassword = \"{}\"\nlocal_player_id = 1\nitem_mapping = []\n", PASSWORD);
+        file_utils::write_file(&format!("{}{}", seed_path, APP_CONFIG_FILE_NAME), app_config).unwrap();
+        keep_previous_app_config(seed).unwrap();
+
+        let game = Game { seed: seed.to_string(), server_url: "localhost:38281".to_string(), you: Player { id: 1, name: "Lemeza".to_string() }, password: PASSWORD.to_string() };
+        let config = LaMulanaConfig { version: "1.6.6.2".to_string(), save_path: "save/".to_string(), rcd_digest: String::new(), dat_digest: String::new(), effects_digest: String::new() };
+        let ap_data = APData { config, games: vec![game], active_game: None };
+        export_seed(&ap_data, seed, Some(bundle_path.clone())).unwrap();
+
+        let mut bundle = ZipArchive::new(File::open(&bundle_path).unwrap()).unwrap();
+        let mut entry_names = Vec::new();
+        for index in 0..bundle.len() {
+            let mut entry = bundle.by_index(index).unwrap();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).unwrap();
+            assert!(!String::from_utf8_lossy(&contents).contains(PASSWORD), "{} contains the password", entry.name());
+            entry_names.push(entry.name().to_string());
+        }
+        assert!(entry_names.contains(&format!("{}{}", SEED_DIR, PREVIOUS_APP_CONFIG_FILE_NAME)));
+
+        std::fs::remove_dir_all(&seed_path).unwrap();
+        std::fs::remove_file(&bundle_path).unwrap();
+    }
+
+    #[test]
+    fn logs_are_scrubbed_of_every_password() {
+        let log = b"Connecting with password old-password\nConnecting with password new-password\n";
+        let redacted_log = redact_log(log, &["new-password".to_string(), "old-password".to_string(), String::new()]);
+        assert_eq!(redacted_log, "Connecting with password <redacted>\nConnecting with password <redacted>\n");
+    }
+}
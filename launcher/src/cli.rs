@@ -0,0 +1,292 @@
+use archipelago_api::api::SlotData;
+use log::debug;
+use std::path::Path;
+
+use crate::ap_data::{APData, Game, Player};
+use crate::bundle;
+use crate::consts::SLOT_DATA_FILE_NAME;
+use crate::file_gen::app_config::AppConfig;
+use crate::file_gen::generator;
+use crate::file_utils;
+use crate::inspector;
+use crate::migration::{self, MigrationOptions};
+use crate::snapshots;
+use crate::verifier;
+
+pub static USAGE: &str = "Usage:
+  la-mulana-multiworld-launch                        Open the launcher window
+  la-mulana-multiworld-launch generate --server <url> --name <player> [--password <password>]
+  la-mulana-multiworld-launch generate --slot-data <file.json or seed directory> [--server <url>] [--password <password>]
+  la-mulana-multiworld-launch list
+  la-mulana-multiworld-launch load <seed>
+  la-mulana-multiworld-launch delete <seed>
+  la-mulana-multiworld-launch regenerate <seed>
+  la-mulana-multiworld-launch edit <seed> [--server <url>] [--password <password>] [--name <player>] [--no-verify]
+  la-mulana-multiworld-launch export <seed> [<bundle.zip>]
+  la-mulana-multiworld-launch import <bundle.zip>
+  la-mulana-multiworld-launch inspect <seed> [<save file>]
+  la-mulana-multiworld-launch migrate <seed> <old save> [--old-config <lamulana-config.toml>] [--output <save file>]
+  la-mulana-multiworld-launch snapshots <seed>
+  la-mulana-multiworld-launch restore-snapshot <seed> <snapshot>
+  la-mulana-multiworld-launch restore
+  la-mulana-multiworld-launch launch";
+
+#[derive(Debug)]
+pub enum Command {
+    Generate(GenerateOptions),
+    List,
+    Load(String),
+    Delete(String),
+    Regenerate(String),
+    Edit(EditOptions),
+    Export(String, Option<String>),
+    Import(String),
+    Inspect(String, Option<String>),
+    Migrate(String, MigrationOptions),
+    Snapshots(String),
+    RestoreSnapshot(String, String),
+    Restore,
+    Launch
+}
+
+#[derive(Debug, Default)]
+pub struct GenerateOptions {
+    server_url: Option<String>,
+    player_name: Option<String>,
+    password: String,
+    slot_data_path: Option<String>
+}
+
+#[derive(Debug, Default)]
+pub struct EditOptions {
+    seed: String,
+    server_url: Option<String>,
+    password: Option<String>,
+    player_name: Option<String>,
+    verify: bool
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, arguments) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    match (command.as_str(), arguments) {
+        ("generate", arguments) => parse_generate_args(arguments).map(Command::Generate),
+        ("list", []) => Ok(Command::List),
+        ("load", [seed]) => Ok(Command::Load(seed.clone())),
+        ("delete", [seed]) => Ok(Command::Delete(seed.clone())),
+        ("regenerate", [seed]) => Ok(Command::Regenerate(seed.clone())),
+        ("edit", [seed, arguments @ ..]) => parse_edit_args(seed, arguments).map(Command::Edit),
+        ("export", [seed]) => Ok(Command::Export(seed.clone(), None)),
+        ("export", [seed, bundle_path]) => Ok(Command::Export(seed.clone(), Some(bundle_path.clone()))),
+        ("import", [bundle_path]) => Ok(Command::Import(bundle_path.clone())),
+        ("inspect", [seed]) => Ok(Command::Inspect(seed.clone(), None)),
+        ("inspect", [seed, save_file_path]) => Ok(Command::Inspect(seed.clone(), Some(save_file_path.clone()))),
+        ("migrate", [seed, old_save_path, arguments @ ..]) => parse_migrate_args(old_save_path, arguments).map(|options| Command::Migrate(seed.clone(), options)),
+        ("snapshots", [seed]) => Ok(Command::Snapshots(seed.clone())),
+        ("restore-snapshot", [seed, snapshot]) => Ok(Command::RestoreSnapshot(seed.clone(), snapshot.clone())),
+        ("restore", []) => Ok(Command::Restore),
+        ("launch", []) => Ok(Command::Launch),
+        _ => Err(USAGE.to_string())
+    }
+}
+
+fn parse_generate_args(arguments: &[String]) -> Result<GenerateOptions, String> {
+    let mut options = GenerateOptions::default();
+    let mut arguments = arguments.iter();
+    while let Some(flag) = arguments.next() {
+        let value = arguments.next().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, USAGE))?.clone();
+        match flag.as_str() {
+            "--server" => options.server_url = Some(value),
+            "--name" => options.player_name = Some(value),
+            "--password" => options.password = value,
+            "--slot-data" => options.slot_data_path = Some(value),
+            _ => return Err(format!("Unknown option {}\n\n{}", flag, USAGE))
+        }
+    }
+
+    if options.slot_data_path.is_none() && (options.server_url.is_none() || options.player_name.is_none()) {
+        return Err(format!("generate needs either --slot-data, or both --server and --name\n\n{}", USAGE));
+    }
+    Ok(options)
+}
+
+fn parse_edit_args(seed: &str, arguments: &[String]) -> Result<EditOptions, String> {
+    let mut options = EditOptions { seed: seed.to_string(), verify: true, ..Default::default() };
+    let mut arguments = arguments.iter();
+    while let Some(flag) = arguments.next() {
+        if flag == "--no-verify" {
+            options.verify = false;
+            continue;
+        }
+        let value = arguments.next().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, USAGE))?.clone();
+        match flag.as_str() {
+            "--server" => options.server_url = Some(value),
+            "--password" => options.password = Some(value),
+            "--name" => options.player_name = Some(value),
+            _ => return Err(format!("Unknown option {}\n\n{}", flag, USAGE))
+        }
+    }
+    Ok(options)
+}
+
+fn parse_migrate_args(old_save_path: &str, arguments: &[String]) -> Result<MigrationOptions, String> {
+    let mut options = MigrationOptions { old_save_path: old_save_path.to_string(), old_app_config_path: None, output_path: None };
+    let mut arguments = arguments.iter();
+    while let Some(flag) = arguments.next() {
+        let value = arguments.next().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, USAGE))?.clone();
+        match flag.as_str() {
+            "--old-config" => options.old_app_config_path = Some(value),
+            "--output" => options.output_path = Some(value),
+            _ => return Err(format!("Unknown option {}\n\n{}", flag, USAGE))
+        }
+    }
+    Ok(options)
+}
+
+pub async fn run(command: Command) -> Result<(), String> {
+    let lm_config = verifier::verify_install()?;
+    let mut ap_data = APData::new(lm_config)?;
+
+    match command {
+        Command::Generate(options) => {
+            let seed = generate(&mut ap_data, options).await?;
+            println!("Generated and loaded seed {}", seed);
+        },
+        Command::List => {
+            for game in &ap_data.games {
+                let active = ap_data.active_game.as_ref().is_some_and(|active_game| active_game.seed == game.seed);
+                println!("{} {} ({} on {})", if active { "*" } else { " " }, game.seed, game.you.name, game.server_url);
+            }
+        },
+        Command::Load(seed) => {
+            ap_data.load_game(seed.clone())?;
+            println!("Loaded seed {}", seed);
+        },
+        Command::Delete(seed) => {
+            ap_data.delete_game(seed.clone())?;
+            println!("Deleted seed {}", seed);
+        },
+        Command::Regenerate(seed) => {
+            let report = ap_data.regenerate_game(seed.clone())?;
+            for warning in report.warnings() {
+                eprintln!("Warning: {}", warning);
+            }
+            println!("Regenerated seed {}, its saves were kept", seed);
+        },
+        Command::Edit(options) => {
+            edit(&mut ap_data, options).await?;
+        },
+        Command::Export(seed, bundle_path) => {
+            let bundle_path = bundle::export_seed(&ap_data, &seed, bundle_path)?;
+            println!("Exported seed {} to {}", seed, bundle_path);
+        },
+        Command::Import(bundle_path) => {
+            let imported_bundle = bundle::import_seed(&mut ap_data, &bundle_path)?;
+            println!("Imported seed {}", imported_bundle.seed);
+            if imported_bundle.password_redacted {
+                eprintln!("Warning: the bundle's password was removed, set it with edit {} --password <password>", imported_bundle.seed);
+            }
+        },
+        Command::Inspect(seed, save_file_path) => {
+            println!("{}", inspector::inspect_seed(&ap_data, &seed, save_file_path)?);
+        },
+        Command::Migrate(seed, options) => {
+            let report = migration::migrate_save(&ap_data, &seed, options)?;
+            if !report.dropped_locations.is_empty() {
+                eprintln!("Warning: checked locations {:?} aren't part of the seed anymore and were left out", report.dropped_locations);
+            }
+            println!("Wrote {} with {} checked locations", report.output_path, report.checked_locations);
+        },
+        Command::Snapshots(seed) => {
+            for snapshot in snapshots::list_snapshots(&seed)? {
+                println!("{}  {}", snapshot.name, snapshot.label());
+            }
+        },
+        Command::RestoreSnapshot(seed, snapshot) => {
+            snapshots::restore_snapshot(&ap_data, &seed, &snapshot)?;
+            println!("Restored snapshot {} of seed {}", snapshot, seed);
+        },
+        Command::Restore => {
+            ap_data.restore_original_files()?;
+            println!("Restored original game files");
+        },
+        Command::Launch => {
+            if !ap_data.seed_selected() {
+                return Err("No seed is loaded, load one before launching".to_string());
+            }
+            snapshots::take_launch_snapshot(&ap_data);
+            crate::launch_game().await;
+        }
+    }
+
+    Ok(())
+}
+
+// Slot data from a file needs no connection, the server and password are only written into the config for the DLL
+async fn generate(ap_data: &mut APData, options: GenerateOptions) -> Result<String, String> {
+    let server_url = options.server_url.clone().unwrap_or_default();
+    let (slot_data, slot_data_bytes) = match &options.slot_data_path {
+        Some(slot_data_path) => read_slot_data(slot_data_path)?,
+        None => {
+            let player_name = options.player_name.clone().unwrap_or_default();
+            crate::verify_new_seed(server_url.clone(), options.password.clone(), player_name).await.map_err(|e| e.to_string())?
+        }
+    };
+
+    let player_name = match &options.player_name {
+        Some(player_name) => player_name.clone(),
+        None => slot_data.players.iter().find(|player| player.id == slot_data.player_id).map(|player| player.name.clone()).ok_or_else(|| {
+            format!("Player {} is missing from the slot data players", slot_data.player_id)
+        })?
+    };
+
+    // Generating again would overwrite the seed's save file
+    let local_seed_name = format!("{}-{}", slot_data.seed, slot_data.player_id);
+    if ap_data.games.iter().any(|game| game.seed == local_seed_name) {
+        return Err(format!("Seed {} already exists, delete it first to generate it again", local_seed_name));
+    }
+
+    let app_config = AppConfig::new(server_url.clone(), options.password.clone(), slot_data.player_id, slot_data.players.clone());
+    generator::generate_files(app_config, slot_data_bytes, local_seed_name.clone(), true).map_err(|e| {
+        debug!("Files failed to Generate: {:?}", e);
+        e.to_string()
+    })?;
+
+    let game = Game {
+        seed: local_seed_name.clone(),
+        server_url,
+        you: Player { id: slot_data.player_id, name: player_name },
+        password: options.password
+    };
+    ap_data.add_new_game(game)?;
+
+    Ok(local_seed_name)
+}
+
+// Anything not passed keeps its stored value
+async fn edit(ap_data: &mut APData, options: EditOptions) -> Result<(), String> {
+    let game = ap_data.game(&options.seed).ok_or_else(|| format!("Couldn't find Seed {}", options.seed))?;
+    let server_url = options.server_url.unwrap_or_else(|| game.server_url.clone());
+    let password = options.password.unwrap_or_else(|| game.password.clone());
+    let player_name = options.player_name.unwrap_or_else(|| game.you.name.clone());
+
+    if options.verify {
+        crate::verify_existing_seed(&options.seed, server_url.clone(), password.clone(), player_name.clone()).await?;
+    }
+    ap_data.update_connection(options.seed.clone(), server_url.clone(), password, player_name.clone())?;
+    println!("Seed {} now connects to {} as {}", options.seed, server_url, player_name);
+    Ok(())
+}
+
+// Also accepts a seed directory, which holds the slot data it was generated from. The file's bytes are stored with the new seed unchanged
+fn read_slot_data(slot_data_path: &str) -> Result<(SlotData, Vec<u8>), String> {
+    let slot_data_path = if Path::new(slot_data_path).is_dir() {
+        format!("{}/{}", slot_data_path.trim_end_matches(['/', '\\']), SLOT_DATA_FILE_NAME)
+    } else {
+        slot_data_path.to_string()
+    };
+    let slot_data_bytes = file_utils::read_file(&slot_data_path)?;
+    let slot_data = serde_json::from_slice::<SlotData>(&slot_data_bytes).map_err(|e| {
+        format!("Error {} while attempting to deserialize Slot Data from {}", e, slot_data_path)
+    })?;
+    Ok((slot_data, slot_data_bytes))
+}
@@ -2,6 +2,20 @@ use std::sync::LazyLock;
 
 pub static AP_PATH: &str = "ap/";
 pub static AP_DATA_PATH: LazyLock<String> = LazyLock::new(|| { format!("{}ap_data.json", AP_PATH) });
+pub static SLOT_DATA_FILE_NAME: &str = "slot_data.json";
+pub static APP_CONFIG_FILE_NAME: &str = "lamulana-config.toml";
+pub static SETTINGS_FILE_NAME: &str = "lamulana-settings.toml";
+pub static PREVIOUS_APP_CONFIG_FILE_NAME: &str = "lamulana-config.previous.toml";
+pub static GENERATION_RECORD_FILE_NAME: &str = "generation.json";
+pub static ROTATION_JOURNAL_PATH: LazyLock<String> = LazyLock::new(|| { format!("{}rotation.json", AP_PATH) });
+pub static ROTATION_BACKUP_PATH: LazyLock<String> = LazyLock::new(|| { format!("{}rotation_backup/", AP_PATH) });
+pub static SNAPSHOT_LIMIT: usize = 20;
+pub static SNAPSHOT_METADATA_FILE_NAME: &str = "snapshot.json";
+pub static EXPORTS_PATH: LazyLock<String> = LazyLock::new(|| { format!("{}exports/", AP_PATH) });
+
+pub static LAUNCHER_LOG_PATH: &str = "lmmw_launcher.txt";
+pub static DLL_LOG_PATH: &str = "lamulanamw.txt";
+pub static DLL_NAME: &str = "LaMulanaMW.dll";
 
 pub static LAMULANA_EXECUTABLE_NAME: &str = "LaMulanaWin";
 pub static LAMULANA_EXECUTABLE_NAME_WITH_EXTENSION: LazyLock<String> = LazyLock::new(|| { format!("{}.exe", LAMULANA_EXECUTABLE_NAME) });
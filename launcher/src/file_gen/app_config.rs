@@ -1,9 +1,15 @@
 use archipelago_api::api::{ArchipelagoPlayer, ItemData, Location};
-use log::debug;
+use game_versions::DEFAULT_MESSAGE_FEED_TYPES;
+use log::{debug, warn};
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
 
+use crate::consts::{DLL_LOG_PATH, SETTINGS_FILE_NAME};
+use crate::file_utils;
 use crate::file_gen::generator::FileGenerationError;
-use crate::file_gen::lm_consts::{GLOBAL_FLAGS, ITEM_CODES};
+use crate::file_gen::lm_consts::{GLOBAL_FLAGS, ITEM_CODES, MAX_COINS, MAX_WEIGHTS, PROGRESSIVE_ITEMS, SUBWEAPON_AMMO};
+
+const HELL_TEMPLE_REWARD_LOCATION: &str = "Hell Temple Reward";
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AppConfig {
@@ -13,10 +19,110 @@ pub struct AppConfig {
     pub log_file_name: String,
     pub local_player_id: i64,
     pub log_level: String,
+    pub item_summary_threshold: usize,
+    pub message_feed_types: Vec<String>,
+    pub hint_hotkey: String,
+    pub hint_items: Vec<i64>,
+    pub shop_scouting: String,
+    pub goal: String,
+    pub goal_guardians: u8,
+    pub hell_temple_flag: Option<i16>,
+    pub shop_scouts: Vec<ShopScout>,
+    pub filler_items: Vec<FillerItem>,
+    pub coin_chests: Vec<CoinChest>,
+    pub progressive_items: Vec<ProgressiveItem>,
     pub players: Vec<ArchipelagoPlayer>,
     pub item_mapping: Vec<ArchipelagoItem>
 }
 
+// Only the fields every launcher version has written, so configs from older launchers still load.
+// The settings a player may have edited are optional for the same reason
+#[derive(Deserialize)]
+pub struct StoredAppConfig {
+    pub server_url: String,
+    pub password: String,
+    pub local_player_id: i64,
+    pub item_mapping: Vec<ArchipelagoItem>,
+    pub item_summary_threshold: Option<usize>,
+    pub message_feed_types: Option<Vec<String>>,
+    pub hint_hotkey: Option<String>,
+    pub shop_scouting: Option<String>
+}
+
+impl StoredAppConfig {
+    pub fn read(app_config_path: &str) -> Result<StoredAppConfig, FileGenerationError> {
+        let serialized_app_config = file_utils::read_file_as_string(app_config_path).map_err(|_| FileGenerationError::AppConfigReadFailure)?;
+        toml::from_str::<StoredAppConfig>(&serialized_app_config).map_err(|e| {
+            debug!("App Config Deserialization Failure with error: {}", e);
+            FileGenerationError::AppConfigReadFailure
+        })
+    }
+}
+
+// Player preferences shared by every seed. The DLL reads them over lamulana-config.toml, which is replaced on every rotation
+#[derive(Serialize)]
+pub struct Settings {
+    pub item_summary_threshold: usize,
+    pub message_feed_types: Vec<String>,
+    pub hint_hotkey: String,
+    pub shop_scouting: String
+}
+
+const SETTINGS_HEADER: &str = "# Applies to every seed and takes precedence over the same keys in lamulana-config.toml.\n\
+# shop_scouting is \"hint\", \"scout\" or \"off\". Delete this file to restore the defaults.\n\n";
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            item_summary_threshold: 5,
+            message_feed_types: DEFAULT_MESSAGE_FEED_TYPES.iter().map(|message_type| message_type.to_string()).collect(),
+            hint_hotkey: "F9".to_string(),
+            shop_scouting: "hint".to_string()
+        }
+    }
+}
+
+impl Settings {
+    // An existing file belongs to the player and is never overwritten
+    pub fn write_default() -> Result<(), String> {
+        if file_utils::path_exists(SETTINGS_FILE_NAME, false)? {
+            return Ok(());
+        }
+
+        let serialized_settings = toml::to_string(&Settings::default()).map_err(|e| format!("Error {} while attempting to serialize settings", e))?;
+        file_utils::write_file(SETTINGS_FILE_NAME, format!("{}{}", SETTINGS_HEADER, serialized_settings))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShopScout {
+    pub shop: u8,
+    pub location_ids: Vec<i64>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FillerItem {
+    pub ap_id: i64,
+    pub item_name: String,
+    pub item_id: u32,
+    pub inventory_slot: usize,
+    pub quantity: u16,
+    pub max_quantity: u16
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Copy)]
+pub struct CoinChest {
+    pub location_id: i64,
+    pub quantity: i16
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProgressiveItem {
+    pub ap_id: i64,
+    pub item_name: String,
+    pub tiers: Vec<i64>
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Copy)]
 pub struct ArchipelagoItem {
     pub flag: i16,
@@ -27,18 +133,46 @@ pub struct ArchipelagoItem {
 
 impl AppConfig {
     pub fn new(server_url: String, password: String, player_id: i64, players: Vec<ArchipelagoPlayer>) -> Self {
+        let settings = Settings::default();
         AppConfig {
             server_url,
             password,
-            log_file_name: "lamulanamw.txt".to_string(),
+            log_file_name: DLL_LOG_PATH.to_string(),
             local_player_id: player_id,
             log_level: "DEBUG".to_string(),
+            item_summary_threshold: settings.item_summary_threshold,
+            message_feed_types: settings.message_feed_types,
+            hint_hotkey: settings.hint_hotkey,
+            hint_items: Vec::new(),
+            shop_scouting: settings.shop_scouting,
+            goal: "escape".to_string(),
+            goal_guardians: 8,
+            hell_temple_flag: None,
+            shop_scouts: Vec::new(),
+            filler_items: Vec::new(),
+            coin_chests: Vec::new(),
+            progressive_items: Vec::new(),
             players,
             item_mapping: Vec::new(),
             next_filler_flag: GLOBAL_FLAGS["filler_items"]
         }
     }
 
+    pub fn keep_settings(&mut self, stored_app_config: &StoredAppConfig) {
+        if let Some(item_summary_threshold) = stored_app_config.item_summary_threshold {
+            self.item_summary_threshold = item_summary_threshold;
+        }
+        if let Some(message_feed_types) = &stored_app_config.message_feed_types {
+            self.message_feed_types = message_feed_types.clone();
+        }
+        if let Some(hint_hotkey) = &stored_app_config.hint_hotkey {
+            self.hint_hotkey = hint_hotkey.clone();
+        }
+        if let Some(shop_scouting) = &stored_app_config.shop_scouting {
+            self.shop_scouting = shop_scouting.clone();
+        }
+    }
+
     pub fn add_item(&mut self, item: ItemData, item_id: i16, location: &Location) -> Result<i16, FileGenerationError> {
         let flag = match item.obtain_flag {
             Some(obtain_flag) => {
@@ -70,9 +204,114 @@ impl AppConfig {
 
         self.item_mapping.push(ap_item);
 
+        if location.name == HELL_TEMPLE_REWARD_LOCATION {
+            self.hell_temple_flag = Some(flag);
+        }
+
         Ok(flag)
     }
 
+    // Slot data without a Goal option predates goal variants, so it falls back to escaping La-Mulana
+    pub fn set_goal(&mut self, options: &HashMap<String, u64>) {
+        self.goal = match options.get("Goal").copied().unwrap_or(0) {
+            1 => "guardians",
+            2 => "ankh_jewels",
+            3 => "hell_temple",
+            _ => "escape"
+        }.to_string();
+        self.goal_guardians = options.get("GuardiansRequired").copied().unwrap_or(8).min(8) as u8;
+
+        if self.goal == "hell_temple" && self.hell_temple_flag.is_none() {
+            warn!("Hell Temple goal selected, but {} is not in the slot data, falling back to the escape", HELL_TEMPLE_REWARD_LOCATION);
+            self.goal = "escape".to_string();
+        }
+    }
+
+    // Weights, coins and ammo are stacked into their inventory slot by the DLL instead of being given as an item
+    pub fn set_filler_items(&mut self, item_table: &HashMap<String, ItemData>) {
+        let mut filler_items = item_table.iter()
+            .filter(|(_, item)| item.game_code >= ITEM_CODES["Weights"])
+            .map(|(item_name, item)| {
+                let max_quantity = match item.game_code {
+                    code if code == ITEM_CODES["Weights"] => MAX_WEIGHTS,
+                    code if code == ITEM_CODES["Coins"] => MAX_COINS,
+                    _ => SUBWEAPON_AMMO.get(item_name.as_str()).map_or(u16::MAX, |max_ammo| *max_ammo as u16)
+                };
+                let quantity = std::cmp::max(item.quantity, 1) as u16;
+                // Names like "50 Coins" already carry the amount
+                let item_name = if item_name.starts_with(|character: char| character.is_ascii_digit()) || quantity == 1 {
+                    item_name.clone()
+                } else {
+                    format!("{} {}", quantity, item_name)
+                };
+
+                FillerItem {
+                    ap_id: item.code as i64,
+                    item_name,
+                    item_id: item.game_code as u32,
+                    inventory_slot: item.game_code as usize,
+                    quantity,
+                    max_quantity
+                }
+            }).collect::<Vec<FillerItem>>();
+        filler_items.sort_by_key(|filler_item| filler_item.ap_id);
+        self.filler_items = filler_items;
+    }
+
+    pub fn set_coin_chests(&mut self, coin_chests: &HashMap<i64, i16>) {
+        let mut coin_chests = coin_chests.iter().map(|(location_id, quantity)| CoinChest {
+            location_id: *location_id,
+            quantity: *quantity
+        }).collect::<Vec<CoinChest>>();
+        coin_chests.sort_by_key(|coin_chest| coin_chest.location_id);
+        self.coin_chests = coin_chests;
+    }
+
+    // Ids come from the item table, a progressive item is left out unless the APworld defines it and every one of its tiers
+    pub fn set_progressive_items(&mut self, item_table: &HashMap<String, ItemData>) {
+        let mut progressive_items = PROGRESSIVE_ITEMS.iter().filter_map(|(item_name, tier_names)| {
+            let progressive_item = item_table.get(*item_name)?;
+            let tiers = tier_names.iter()
+                .map(|tier_name| item_table.get(*tier_name).map(|tier| tier.code as i64))
+                .collect::<Option<Vec<i64>>>()?;
+            Some(ProgressiveItem { ap_id: progressive_item.code as i64, item_name: item_name.to_string(), tiers })
+        }).collect::<Vec<ProgressiveItem>>();
+        progressive_items.sort_by_key(|progressive_item| progressive_item.ap_id);
+        self.progressive_items = progressive_items;
+    }
+
+    // Progression items for this slot, in a stable order so the DLL hints them one after another
+    pub fn set_hint_items(&mut self, item_table: &HashMap<String, ItemData>) {
+        let mut hint_items = item_table.values()
+            .filter(|item| item.progression)
+            .map(|item| item.code as i64)
+            .collect::<Vec<i64>>();
+        hint_items.sort();
+        hint_items.dedup();
+        self.hint_items = hint_items;
+    }
+
+    // Shop locations grouped by the number Dat gave each shop card
+    pub fn set_shop_scouts(&mut self, shop_numbers: &HashMap<usize, u8>, locations: &[Location]) {
+        let mut shop_scouts: HashMap<u8, Vec<i64>> = HashMap::new();
+        for location in locations.iter().filter(|location| location.slot.is_some()) {
+            let (Some(address), Some(cards)) = (location.address, &location.cards) else { continue };
+            for card in cards {
+                if let Some(shop_number) = shop_numbers.get(&(*card as usize)) {
+                    shop_scouts.entry(*shop_number).or_default().push(address);
+                }
+            }
+        }
+
+        let mut shop_scouts = shop_scouts.into_iter().map(|(shop, mut location_ids)| {
+            location_ids.sort();
+            location_ids.dedup();
+            ShopScout { shop, location_ids }
+        }).collect::<Vec<ShopScout>>();
+        shop_scouts.sort_by_key(|shop_scout| shop_scout.shop);
+        self.shop_scouts = shop_scouts;
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>, FileGenerationError> {
         let app_config = toml::to_vec(&self).map_err(|e| {
             debug!("Serilization Failure with error: {}", e);
@@ -351,6 +351,33 @@ impl Dat {
         Ok(())
     }
 
+    // Numbers every shop holding a placed item and makes its greeting set shop_opened to that number, so the DLL knows which shop to scout
+    pub fn add_shop_opened_flags(&mut self) -> Result<HashMap<usize, u8>, FileGenerationError> {
+        let mut card_indices = self.shop_placements.keys().cloned().collect::<Vec<usize>>();
+        card_indices.sort();
+
+        let mut shop_numbers = HashMap::new();
+        for (shop_number, card_index) in card_indices.into_iter().enumerate() {
+            let shop_number = u8::try_from(shop_number + 1).map_err(|_| FileGenerationError::MalformedDatFile)?;
+            let entries = &self.dat_file.cards[card_index].contents;
+
+            // The greeting starts at the first break after the shop's data entries
+            let last_data = entries.iter().rposition(|entry| entry.header == HEADERS["data"]).ok_or_else(|| {
+                debug!("Shop is missing Data: {:?}", card_index);
+                FileGenerationError::MalformedDatFile
+            })?;
+            let greeting_start = entries[last_data..].iter().position(|entry| entry.header == HEADERS["break"]).ok_or_else(|| {
+                debug!("Shop is missing Breaks: {:?}", card_index);
+                FileGenerationError::MalformedDatFile
+            })? + last_data + 1;
+
+            self.add_flag_entry(card_index as i16, greeting_start, GLOBAL_FLAGS["shop_opened"], shop_number as i16);
+            shop_numbers.insert(card_index, shop_number);
+        }
+
+        Ok(shop_numbers)
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>, FileGenerationError> {
         let mut writer = Cursor::new(Vec::new());
         self.dat_file.write_be(&mut writer).map_err(|_| FileGenerationError::DatFileWriteFailure)?;
@@ -1,11 +1,19 @@
-use archipelago_api::api::SlotData;
+use archipelago_api::api::{ItemData, Location, SlotData};
+use log::debug;
+use rand::SeedableRng;
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand_chacha::ChaCha20Rng;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use thiserror::Error;
 
-use crate::consts::AP_PATH;
-use crate::file_gen::app_config::AppConfig;
+use crate::consts::{AP_PATH, APP_CONFIG_FILE_NAME, GENERATION_RECORD_FILE_NAME, PREVIOUS_APP_CONFIG_FILE_NAME, SLOT_DATA_FILE_NAME};
+use crate::file_gen::app_config::{AppConfig, StoredAppConfig};
 use crate::file_gen::dat::Dat;
 use crate::file_gen::graphics;
-use crate::file_gen::lm_consts::ITEM_CODES;
+use crate::file_gen::lm_consts::{ITEM_CODES, PROGRESSIVE_ITEMS, RCD_OBJECTS};
 use crate::file_gen::rcd::Rcd;
 use crate::file_gen::sav::Sav;
 use crate::file_utils;
@@ -42,6 +50,10 @@ pub enum FileGenerationError {
     SaveFileModFailure,
     #[error("Failed to write Save File")]
     SaveFileWriteFailure,
+    #[error("Failed to read Save File, it may be corrupted")]
+    SaveFileReadFailure,
+    #[error("Save flag {0:#x} is outside the save's flags, the seed's config may be corrupted")]
+    SaveFlagOutOfRange(usize),
     #[error("Failed to read 01effect.png")]
     EffectsFileOpenFailure,
     #[error("Failed to copy 01effect.png")]
@@ -51,42 +63,167 @@ pub enum FileGenerationError {
     #[error("Failed to serialize lamulana-config.toml")]
     AppConfigSerializeFailure,
     #[error("Failed to write lamulana-config.toml")]
-    AppConfigWriteFailure
+    AppConfigWriteFailure,
+    #[error("Failed to save Slot Data")]
+    SlotDataWriteFailure,
+    #[error("Failed to read the seed's saved Slot Data, it may predate regeneration support")]
+    SlotDataReadFailure,
+    #[error("Failed to read the seed's lamulana-config.toml")]
+    AppConfigReadFailure,
+    #[error("Failed to write the seed's generation record")]
+    GenerationRecordWriteFailure
 }
 
-pub fn generate_files(mut app_config: AppConfig, slot_data: SlotData, seed_name: String) -> Result<(), FileGenerationError>{
-    let mut rcd_file = Rcd::new(slot_data.cursed_chests.clone())?;
-
-    let mut dat_file = Dat::new()?;
-    dat_file.apply_mods()?;
+// Written next to every seed, so regenerating can tell which launcher made the files it replaces
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GenerationRecord {
+    pub launcher_version: String
+}
 
-    let mut sav_file = Sav::new();
-    sav_file.apply_mods(&slot_data)?;
+pub struct RegenerationReport {
+    pub previous_version: Option<String>,
+    // Locations whose item flag moved, a save made with the old files may already have the old flags set
+    pub changed_locations: Vec<i64>
+}
 
-    for slot_data_location in slot_data.locations.iter() {
-        match &slot_data_location.address {
-            None => { continue; },
-            _ => ()
+impl RegenerationReport {
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        match &self.previous_version {
+            Some(previous_version) if previous_version != env!("CARGO_PKG_VERSION") => {
+                warnings.push(format!("Seed was generated by launcher {}, now {}", previous_version, env!("CARGO_PKG_VERSION")));
+            },
+            None => warnings.push("Seed has no generation record, it was made by an older launcher".to_string()),
+            _ => {}
+        }
+        if !self.changed_locations.is_empty() {
+            warnings.push(format!("{} location flags changed, existing saves may show wrong checks or items until they are migrated", self.changed_locations.len()));
         }
+        warnings
+    }
+}
 
-        let ap_item = match &slot_data_location.item {
-            Some(item) => item,
-            None => { continue; }
-        };
+// Every random choice made while generating files comes from here, so the same seed and slot always give the same files.
+// ChaCha20 rather than StdRng, whose algorithm may change between rand releases
+pub fn seeded_rng(seed: &str, player_id: i64) -> ChaCha20Rng {
+    let digest: [u8; 32] = Sha256::digest(format!("{}:{}", seed, player_id)).into();
+    ChaCha20Rng::from_seed(digest)
+}
 
+// What goes in one location, worked out from slot data alone before any of the game's files are read
+#[derive(Clone, Debug)]
+pub struct Placement {
+    pub location: Location,
+    pub lm_item: ItemData,
+    pub item_id: i16,
+    // Gold in the chest, for coin items and for filler that becomes a coin chest
+    pub coin_quantity: Option<i16>
+}
+
+// Every random choice made while placing items happens here, so placements can be checked without the game's files
+pub fn plan_placements(slot_data: &SlotData, local_player_id: i64, rng: &mut ChaCha20Rng) -> Vec<Placement> {
+    let coin_chest_quantities = [(200, 1), (100, 2), (50, 1), (30, 4), (10, 6), (1, 2)];
+    let distribution = WeightedIndex::new(coin_chest_quantities.iter().map(|quantity| quantity.1)).unwrap();
+
+    slot_data.locations.iter().filter(|location| location.address.is_some()).filter_map(|location| {
+        let ap_item = location.item.as_ref()?;
         let lm_item = match slot_data.item_table.get(&ap_item.name) {
             Some(item) => item.clone(),
-            None => {
-                if ap_item.player == app_config.local_player_id {
-                    continue;
-                }
-                Default::default()
-            }
+            None if ap_item.player == local_player_id => return None,
+            None => Default::default()
+        };
+
+        // Local progressive copies are placed like items for other players, the server sends them back and the DLL picks the tier on arrival
+        let is_progressive = PROGRESSIVE_ITEMS.contains_key(ap_item.name.as_str());
+        let item_id = if lm_item.game_code == 0 || ap_item.player != local_player_id || is_progressive { ITEM_CODES["Holy Grail (Full)"] } else { lm_item.game_code };
+
+        // Coin fillers keep their amount, a Shell Horn filler in a chest becomes a coin chest with a random one
+        let is_chest = location.file_type.as_deref() == Some("rcd") && location.object_type == Some(RCD_OBJECTS["chest"]);
+        let coin_quantity = if item_id == ITEM_CODES["Coin Chest"] {
+            Some(lm_item.quantity)
+        } else if is_chest && item_id == ITEM_CODES["Shell Horn"] {
+            Some(coin_chest_quantities[distribution.sample(rng)].0)
+        } else {
+            None
         };
 
-        let item_id = if lm_item.game_code == 0 || ap_item.player != app_config.local_player_id { ITEM_CODES["Holy Grail (Full)"] } else { lm_item.game_code };
+        Some(Placement { location: location.clone(), lm_item, item_id, coin_quantity })
+    }).collect()
+}
+
+// Slot data is kept as the server sent it, so fields SlotData doesn't read yet are still there when the seed is regenerated
+pub fn parse_slot_data(slot_data_bytes: &[u8]) -> Result<SlotData, FileGenerationError> {
+    serde_json::from_slice::<SlotData>(slot_data_bytes).map_err(|e| {
+        debug!("Slot Data Deserialization Failure with error: {}", e);
+        FileGenerationError::MalformedSlotData
+    })
+}
+
+fn read_stored_slot_data_bytes(seed_name: &str) -> Result<Vec<u8>, FileGenerationError> {
+    file_utils::read_file(&format!("{}{}/{}", AP_PATH, seed_name, SLOT_DATA_FILE_NAME)).map_err(|_| FileGenerationError::SlotDataReadFailure)
+}
+
+pub fn read_stored_slot_data(seed_name: &str) -> Result<SlotData, FileGenerationError> {
+    parse_slot_data(&read_stored_slot_data_bytes(seed_name)?)
+}
+
+// Rebuilds a seed from its saved slot data and config, leaving its save directory alone
+pub fn regenerate_files(seed_name: String) -> Result<RegenerationReport, FileGenerationError> {
+    let seed_path = format!("{}{}", AP_PATH, seed_name);
+
+    let slot_data_bytes = read_stored_slot_data_bytes(&seed_name)?;
+    let slot_data = parse_slot_data(&slot_data_bytes)?;
+    let app_config_path = format!("{}/{}", seed_path, APP_CONFIG_FILE_NAME);
+    let previous_app_config = StoredAppConfig::read(&app_config_path)?;
+    keep_previous_app_config(&seed_name)?;
+
+    let previous_version = file_utils::read_file_as_string(&format!("{}/{}", seed_path, GENERATION_RECORD_FILE_NAME)).ok()
+        .and_then(|serialized_record| serde_json::from_str::<GenerationRecord>(&serialized_record).ok())
+        .map(|record| record.launcher_version);
+
+    let mut app_config = AppConfig::new(previous_app_config.server_url.clone(), previous_app_config.password.clone(), previous_app_config.local_player_id, slot_data.players.clone());
+    app_config.keep_settings(&previous_app_config);
+    let new_app_config = generate_files(app_config, slot_data_bytes, seed_name, false)?;
+
+    let previous_flags = previous_app_config.item_mapping.iter().map(|item| (item.location_id, item.flag)).collect::<HashMap<i64, i16>>();
+    let mut changed_locations = new_app_config.item_mapping.iter()
+        .filter(|item| previous_flags.get(&item.location_id).is_some_and(|flag| *flag != item.flag))
+        .map(|item| item.location_id)
+        .collect::<Vec<i64>>();
+    changed_locations.sort();
+
+    Ok(RegenerationReport { previous_version, changed_locations })
+}
+
+// Saves made with the replaced files can only be migrated with the flag layout they were made with.
+// Regenerating again keeps the first copy, so saves that were never migrated still have theirs
+pub fn keep_previous_app_config(seed_name: &str) -> Result<(), FileGenerationError> {
+    let app_config_path = format!("{}{}/{}", AP_PATH, seed_name, APP_CONFIG_FILE_NAME);
+    let previous_app_config_path = format!("{}{}/{}", AP_PATH, seed_name, PREVIOUS_APP_CONFIG_FILE_NAME);
+    if !file_utils::path_exists(&previous_app_config_path, false).map_err(|_| FileGenerationError::AppConfigReadFailure)? {
+        file_utils::copy_file(&app_config_path, &previous_app_config_path).map_err(|_| FileGenerationError::AppConfigWriteFailure)?;
+    }
+    Ok(())
+}
+
+pub fn generate_files(mut app_config: AppConfig, slot_data_bytes: Vec<u8>, seed_name: String, write_save: bool) -> Result<AppConfig, FileGenerationError>{
+    let slot_data = parse_slot_data(&slot_data_bytes)?;
+
+    let placements = plan_placements(&slot_data, app_config.local_player_id, &mut seeded_rng(&slot_data.seed, slot_data.player_id));
+    let mut rcd_file = Rcd::new(slot_data.cursed_chests.clone())?;
+
+    let mut dat_file = Dat::new()?;
+    dat_file.apply_mods()?;
+
+    let mut sav_file = Sav::new();
+    sav_file.apply_mods(&slot_data)?;
+
+    for Placement { location: slot_data_location, lm_item, item_id, coin_quantity } in placements {
         let item_flag = app_config.add_item(lm_item.clone(), item_id, &slot_data_location)?;
 
+        // Coins can only be found in chests, anywhere else they fall back to the Shell Horn filler
+        let item_id = if item_id == ITEM_CODES["Coin Chest"] { ITEM_CODES["Shell Horn"] } else { item_id };
+
         match &slot_data_location.file_type {
             Some(file_type) => {
                 if file_type == "dat" {
@@ -99,7 +236,7 @@ pub fn generate_files(mut app_config: AppConfig, slot_data: SlotData, seed_name:
                         }
                     }
                 } else if file_type == "rcd" {
-                    rcd_file.place_item(&slot_data_location, item_id, item_flag)?;
+                    rcd_file.place_item(&slot_data_location, item_id, item_flag, coin_quantity)?;
                 }
             },
             None => ()
@@ -107,6 +244,13 @@ pub fn generate_files(mut app_config: AppConfig, slot_data: SlotData, seed_name:
     }
 
     dat_file.update_shop_bunemon_text()?;
+    let shop_numbers = dat_file.add_shop_opened_flags()?;
+    app_config.set_shop_scouts(&shop_numbers, &slot_data.locations);
+    app_config.set_hint_items(&slot_data.item_table);
+    app_config.set_goal(&slot_data.options);
+    app_config.set_filler_items(&slot_data.item_table);
+    app_config.set_progressive_items(&slot_data.item_table);
+    app_config.set_coin_chests(rcd_file.coin_chests());
 
     rcd_file.give_starting_items(
         slot_data.start_inventory.clone(),
@@ -130,14 +274,95 @@ pub fn generate_files(mut app_config: AppConfig, slot_data: SlotData, seed_name:
     let dat_file_path = format!("{}/{}", new_seed_path, "script_code.dat");
     file_utils::write_file(&dat_file_path, dat_file.to_bytes()?).map_err(|_| FileGenerationError::DatFileWriteFailure)?;
 
-    let save_file_path = format!("{}/{}", save_file_dir, "lm_00.sav");
-    file_utils::write_file(&save_file_path, sav_file.to_bytes()?).map_err(|_| FileGenerationError::SaveFileWriteFailure)?;
+    if write_save {
+        let save_file_path = format!("{}/{}", save_file_dir, "lm_00.sav");
+        file_utils::write_file(&save_file_path, sav_file.to_bytes()?).map_err(|_| FileGenerationError::SaveFileWriteFailure)?;
+    }
 
     let effects_file_path = format!("{}/{}", new_seed_path, "01effect.png");
     file_utils::write_file(&effects_file_path, effect_bytes).map_err(|_| FileGenerationError::EffectsFileWriteFailure)?;
 
-    let app_config_file_path = format!("{}/{}", new_seed_path, "lamulana-config.toml");
+    let app_config_file_path = format!("{}/{}", new_seed_path, APP_CONFIG_FILE_NAME);
     file_utils::write_file(&app_config_file_path, app_config.to_bytes()?).map_err(|_| FileGenerationError::AppConfigWriteFailure)?;
 
-    Ok(())
+    // Kept with the seed so it can be generated again without the server
+    let slot_data_file_path = format!("{}/{}", new_seed_path, SLOT_DATA_FILE_NAME);
+    file_utils::write_file(&slot_data_file_path, slot_data_bytes).map_err(|_| FileGenerationError::SlotDataWriteFailure)?;
+
+    let generation_record = GenerationRecord { launcher_version: env!("CARGO_PKG_VERSION").to_string() };
+    let generation_record_bytes = serde_json::to_vec_pretty(&generation_record).map_err(|_| FileGenerationError::GenerationRecordWriteFailure)?;
+    let generation_record_file_path = format!("{}/{}", new_seed_path, GENERATION_RECORD_FILE_NAME);
+    file_utils::write_file(&generation_record_file_path, generation_record_bytes).map_err(|_| FileGenerationError::GenerationRecordWriteFailure)?;
+
+    Ok(app_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    const FIXTURE_SLOT_DATA: &str = include_str!("../../fixtures/slot_data.json");
+
+    #[test]
+    fn seeded_rng_gives_the_same_sequence_across_releases() {
+        let mut rng = seeded_rng("12345678901234567890", 1);
+        let values = (0..3).map(|_| rng.next_u64()).collect::<Vec<u64>>();
+        assert_eq!(values, vec![0x2f184c4f5111ded4, 0x83f86338c4679789, 0x2ca52fb8b70b8498]);
+
+        assert_ne!(seeded_rng("12345678901234567890", 2).next_u64(), values[0]);
+    }
+
+    #[test]
+    fn parse_slot_data_reads_the_fixture_and_rejects_anything_else() {
+        let slot_data = parse_slot_data(FIXTURE_SLOT_DATA.as_bytes()).unwrap();
+        assert_eq!((slot_data.player_id, slot_data.locations.len()), (1, 4));
+        assert!(matches!(parse_slot_data(b"{\"seed\": \"1\"}"), Err(FileGenerationError::MalformedSlotData)));
+    }
+
+    fn planned_items(slot_data: &SlotData, rng_player_id: i64) -> Vec<(Option<i64>, i16, Option<i16>)> {
+        plan_placements(slot_data, slot_data.player_id, &mut seeded_rng(&slot_data.seed, rng_player_id)).iter()
+            .map(|placement| (placement.location.address, placement.item_id, placement.coin_quantity))
+            .collect()
+    }
+
+    #[test]
+    fn plan_placements_is_the_same_for_the_same_seed_and_player() {
+        let slot_data = parse_slot_data(FIXTURE_SLOT_DATA.as_bytes()).unwrap();
+        let expected_items = vec![
+            (Some(2359002), 53, None),
+            (Some(2359005), ITEM_CODES["Shell Horn"], Some(30)),
+            (Some(2359008), ITEM_CODES["Holy Grail (Full)"], None),
+            (Some(2359010), ITEM_CODES["Shell Horn"], Some(100))
+        ];
+        assert_eq!(planned_items(&slot_data, 1), expected_items);
+        assert_eq!(planned_items(&slot_data, 1), expected_items);
+    }
+
+    #[test]
+    fn plan_placements_differs_between_players() {
+        let slot_data = parse_slot_data(FIXTURE_SLOT_DATA.as_bytes()).unwrap();
+        assert_ne!(planned_items(&slot_data, 1), planned_items(&slot_data, 2));
+    }
+
+    // Generates the fixture slot twice and compares every written file. Tests run from launcher/, so the source files go in launcher/ap/source/
+    #[test]
+    #[ignore = "needs the game's script.rcd, script_code.dat and 01effect.png in launcher/ap/source/"]
+    fn generate_files_writes_identical_files_for_the_same_slot() {
+        let slot_data = parse_slot_data(FIXTURE_SLOT_DATA.as_bytes()).unwrap();
+        let seed_names = ["determinism-test-a", "determinism-test-b"];
+        for seed_name in seed_names {
+            let app_config = AppConfig::new("localhost:38281".to_string(), String::new(), slot_data.player_id, slot_data.players.clone());
+            generate_files(app_config, FIXTURE_SLOT_DATA.as_bytes().to_vec(), seed_name.to_string(), true).unwrap();
+        }
+
+        for file_name in ["script.rcd", "script_code.dat", "save/lm_00.sav", "01effect.png", APP_CONFIG_FILE_NAME, SLOT_DATA_FILE_NAME] {
+            let generated_files = seed_names.map(|seed_name| file_utils::read_file(&format!("{}{}/{}", AP_PATH, seed_name, file_name)).unwrap());
+            assert!(generated_files[0] == generated_files[1], "{} differs between runs", file_name);
+        }
+
+        for seed_name in seed_names {
+            file_utils::delete_dir(&format!("{}{}", AP_PATH, seed_name)).unwrap();
+        }
+    }
 }
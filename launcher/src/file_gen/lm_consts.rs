@@ -134,6 +134,8 @@ pub const GLOBAL_FLAGS: LazyLock<HashMap<&'static str, i16>> = LazyLock::new(||
         ("xelpud_conversation_diary_found", 0x866),
         ("received_items_index_1", 0x867),
         ("received_items_index_2", 0x868),
+        ("shop_opened", 0x869),
+        ("received_items_counted", 0x86a),
         ("filler_items", 0x9f6)
     ])
 });
@@ -145,6 +147,8 @@ pub const INVENTORY: LazyLock<HashMap<&'static str, usize>> = LazyLock::new(|| {
             ("axe", 0x5),
             ("katana", 0x6),
             ("axe", 0x5),
+            ("weights", 0x69),
+            ("coins", 0x6a),
             ("shurikens", 0x8),
             ("rolling_shurikens", 0x9),
             ("earth_spears", 0xa),
@@ -286,6 +290,17 @@ pub const STARTING_WEAPONS: LazyLock<HashMap<u64, &'static str>> = LazyLock::new
     ])
 });
 
+// The Leather Whip is owned from the start, so whips begin at the Chain Whip
+pub const PROGRESSIVE_ITEMS: LazyLock<HashMap<&'static str, Vec<&'static str>>> = LazyLock::new(|| {
+    HashMap::from([
+        ("Progressive Whip", vec!["Chain Whip", "Flail Whip"]),
+        ("Progressive Shield", vec!["Buckler", "Silver Shield", "Angel Shield"])
+    ])
+});
+
+pub const MAX_COINS: u16 = 999;
+pub const MAX_WEIGHTS: u16 = 999;
+
 pub const SUBWEAPON_AMMO: LazyLock<HashMap<&str, i16>> = LazyLock::new(|| {
     HashMap::from([
         ("Shuriken Ammo", 150),
@@ -306,7 +321,10 @@ pub const ITEM_CODES: LazyLock<HashMap<&'static str, i16>> = LazyLock::new(|| {
         ("Twin Statue", 59),
         ("Map", 70),
         ("Holy Grail (Full)", 83),
-        ("Weights", 105)
+        ("Weights", 105),
+        ("Coins", 106),
+        // Coin items in the APworld use the chest's coin code instead of an inventory item
+        ("Coin Chest", -10)
     ])
 });
 
@@ -367,6 +385,33 @@ pub const ZONES: LazyLock<Vec<Vec<i32>>> = LazyLock::new(|| {
     ]
 });
 
+pub const ZONE_NAMES: [&'static str; 24] = [
+    "Gate of Guidance",
+    "Surface",
+    "Mausoleum of the Giants",
+    "Temple of the Sun",
+    "Spring in the Sky",
+    "Inferno Cavern",
+    "Chamber of Extinction",
+    "Twin Labyrinths",
+    "Endless Corridor",
+    "Shrine of the Mother",
+    "Gate of Illusion",
+    "Graveyard of the Giants",
+    "Temple of Moonlight",
+    "Tower of the Goddess",
+    "Tower of Ruin",
+    "Chamber of Birth",
+    "Chamber of Birth",
+    "Dimensional Corridor",
+    "True Shrine of the Mother",
+    "Gate of Time",
+    "Gate of Time",
+    "Gate of Time",
+    "Surface",
+    "Hell Temple"
+];
+
 pub const FONT: &'static str = "!\"&'(),-./0123456789:?ABCDEFGHIJKLMNOPQRSTUVWXYZ　]^_\
 abcdefghijklmnopqrstuvwxyz…♪、。々「」ぁあぃいぅうぇえぉおか\
 がきぎくぐけげこごさざしじすずせぜそぞただちぢっつづてでとどなにぬねのはばぱひびぴふぶぷへべぺほ\
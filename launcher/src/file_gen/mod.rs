@@ -1,8 +1,8 @@
 pub mod generator;
 pub mod app_config;
+pub mod lm_consts;
+pub mod sav;
 
 mod dat;
 mod graphics;
-mod lm_consts;
 mod rcd;
-mod sav;
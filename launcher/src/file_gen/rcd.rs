@@ -3,8 +3,6 @@ use binrw::{BinRead, BinWrite, binrw};
 use binrw::helpers::args_iter;
 use log::debug;
 use modular_bitfield::prelude::*;
-use rand::prelude::*;
-use rand::distr::weighted::WeightedIndex;
 use std::collections::HashMap;
 use std::io::Cursor;
 
@@ -147,7 +145,8 @@ pub struct Room {
 
 pub struct Rcd {
     rcd_file: LaMulanaRcd,
-    cursed_chests: Vec<String>
+    cursed_chests: Vec<String>,
+    coin_chests: HashMap<i64, i16>
 }
 
 impl Rcd {
@@ -155,10 +154,15 @@ impl Rcd {
         let raw_file = file_utils::read_file(&SOURCE_RCD_PATH).map_err(|_| FileGenerationError::RcdFileReadFailure)?;
         let mut reader = Cursor::new(raw_file);
         let rcd_file = LaMulanaRcd::read_be(&mut reader).map_err(|_| FileGenerationError::RcdFileParseFailure)?;
-        Ok(Rcd { rcd_file, cursed_chests })
+        Ok(Rcd { rcd_file, cursed_chests, coin_chests: HashMap::new() })
     }
 
-    pub fn place_item(&mut self, location: &Location, original_item_id: i16, new_item_flag: i16) -> Result<(), FileGenerationError> {
+    // Gold in each coin chest by location id, the server only knows these as Shell Horns
+    pub fn coin_chests(&self) -> &HashMap<i64, i16> {
+        &self.coin_chests
+    }
+
+    pub fn place_item(&mut self, location: &Location, original_item_id: i16, new_item_flag: i16, coin_quantity: Option<i16>) -> Result<(), FileGenerationError> {
         let item_type = location.object_type.ok_or_else(|| {
             debug!("Object Type Missing for Rcd Location: {:?}", location);
             FileGenerationError::MalformedSlotData
@@ -236,10 +240,12 @@ impl Rcd {
                                 }
                                 // Coin Chest
                                 if item_id == -10 {
-                                    let coin_chest_quantities = [(200, 1), (100, 2), (50, 1), (30, 4), (10, 6), (1, 2)];
-                                    let distribution = WeightedIndex::new(coin_chest_quantities.iter().map(|quantity| quantity.1)).unwrap();
-                                    let mut rng = rand::rng();
-                                    let quantity = coin_chest_quantities[distribution.sample(&mut rng)].0;
+                                    // The amount is picked with the rest of the placement, see generator::plan_placements
+                                    let quantity = coin_quantity.ok_or_else(|| {
+                                        debug!("Coin quantity is missing for Rcd Location: {:?}", location);
+                                        FileGenerationError::MalformedSlotData
+                                    })?;
+                                    self.coin_chests.insert(address, quantity);
 
                                     screen_object.parameters[1] = quantity;
                                     screen_object.parameters[2] = 0;
@@ -5,7 +5,7 @@ use std::io::Cursor;
 
 use crate::file_gen::generator::FileGenerationError;
 
-use super::lm_consts::{GLOBAL_FLAGS, INVENTORY, STARTING_WEAPONS};
+use super::lm_consts::{GLOBAL_FLAGS, INVENTORY, STARTING_WEAPONS, ZONE_NAMES};
 
 const NUM_EMAILS: u16 = 46;
 
@@ -60,6 +60,14 @@ pub struct BunemonRecord {
     is_tablet: u8
 }
 
+pub struct SavePosition {
+    pub zone: u8,
+    pub room: u8,
+    pub screen: u8,
+    pub x: u16,
+    pub y: u16
+}
+
 pub struct Sav {
     save_file: LaMulanaSav,
     global_flag_lookup: HashMap<&'static str, usize>
@@ -67,7 +75,7 @@ pub struct Sav {
 
 impl Sav {
     pub fn new() -> Self {
-        let global_flag_lookup = GLOBAL_FLAGS.iter().map(|(k,v)| (*k, *v as usize)).collect::<HashMap<&str, usize>>();
+        let global_flag_lookup = Self::global_flag_lookup();
         let save_file = LaMulanaSav {
             valid: 1,
             game_time: 0,
@@ -99,6 +107,61 @@ impl Sav {
         Sav { save_file, global_flag_lookup }
     }
 
+    pub fn from_bytes(save_bytes: &[u8]) -> Result<Self, FileGenerationError> {
+        let save_file = LaMulanaSav::read_be(&mut Cursor::new(save_bytes)).map_err(|_| FileGenerationError::SaveFileReadFailure)?;
+        Ok(Sav { save_file, global_flag_lookup: Self::global_flag_lookup() })
+    }
+
+    pub fn zone_name(&self) -> String {
+        ZONE_NAMES.get(self.save_file.zone as usize).map_or_else(|| format!("Zone {}", self.save_file.zone), |zone_name| zone_name.to_string())
+    }
+
+    pub fn position(&self) -> SavePosition {
+        SavePosition {
+            zone: self.save_file.zone,
+            room: self.save_file.room,
+            screen: self.save_file.screen,
+            x: self.save_file.x_postion,
+            y: self.save_file.y_postion
+        }
+    }
+
+    // Flags come from seed configs, which may not match this save, so none of these index blindly
+    pub fn flag(&self, flag: usize) -> Option<u8> {
+        self.save_file.flags.get(flag).copied()
+    }
+
+    pub fn set_flag(&mut self, flag: usize, value: u8) -> Result<(), FileGenerationError> {
+        let save_flag = self.save_file.flags.get_mut(flag).ok_or(FileGenerationError::SaveFlagOutOfRange(flag))?;
+        *save_flag = value;
+        Ok(())
+    }
+
+    pub fn inventory(&self, inventory_slot: usize) -> Option<u16> {
+        self.save_file.inventory.get(inventory_slot).copied()
+    }
+
+    // Mail numbers in the order they arrived, unused entries hold 0xffff
+    pub fn received_emails(&self) -> Vec<u16> {
+        self.save_file.emails.iter().take(self.save_file.received_emails as usize)
+            .map(|email| email.mail_number)
+            .filter(|mail_number| *mail_number != 0xffff)
+            .collect()
+    }
+
+    pub fn mantras_learned(&self) -> Vec<usize> {
+        self.save_file.mantras_learned.iter().enumerate().filter(|(_, learned)| **learned != 0).map(|(mantra, _)| mantra).collect()
+    }
+
+    pub fn maps_owned(&self) -> Vec<usize> {
+        (0..u32::BITS as usize).filter(|map| self.save_file.maps_owned_bit_array & (1 << map) != 0).collect()
+    }
+
+    // Play time and max HP aren't exposed, how game_time and max_hp scale hasn't been checked against the game yet
+    pub fn current_hp(&self) -> u16 {
+        self.save_file.current_hp
+    }
+
     pub fn apply_mods(&mut self, slot_data: &SlotData) -> Result<(), FileGenerationError> {
         self.set_starting_weapon(slot_data.options["StartingWeapon"])?;
         Ok(())
@@ -212,13 +275,18 @@ impl Sav {
         Ok(())
     }
 
+    fn global_flag_lookup() -> HashMap<&'static str, usize> {
+        GLOBAL_FLAGS.iter().map(|(k,v)| (*k, *v as usize)).collect::<HashMap<&str, usize>>()
+    }
+
     fn default_flags(global_flag_lookup: HashMap<&'static str, usize>) -> [u8; 4096] {
         let mut flags = [0; 4096];
 
         flags[global_flag_lookup["end_start_animation"]] = 1;
         flags[global_flag_lookup["hell_dlc"]] = 1;
         flags[global_flag_lookup["randomizer_save_loaded"]] = 1;
-        flags[global_flag_lookup["received_items_index_2"]] = 1;
+        // The DLL resyncs saves without it from the first received item, since older launchers kept a different index
+        flags[global_flag_lookup["received_items_counted"]] = 1;
 
         flags
     }
@@ -252,3 +320,70 @@ impl Sav {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A save 1:02:03 in, with two emails, two mantras, two maps, the Birth Seal Chest checked and three items received
+    const FIXTURE_SAVE: &[u8] = include_bytes!("../../fixtures/lm_00.sav");
+
+    #[test]
+    fn fixture_save_reads_back_the_bytes_it_was_read_from() {
+        let sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        assert_eq!(sav.to_bytes().unwrap(), FIXTURE_SAVE);
+    }
+
+    #[test]
+    fn fixture_save_fields_are_read() {
+        let sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        let position = sav.position();
+
+        assert_eq!((position.zone, position.room, position.screen, position.x, position.y), (3, 2, 1, 300, 200));
+        assert_eq!(sav.current_hp(), 40);
+        assert_eq!(sav.received_emails(), vec![5, 9]);
+        assert_eq!(sav.mantras_learned(), vec![0, 2]);
+        assert_eq!(sav.maps_owned(), vec![0, 2]);
+        assert_eq!(sav.flag(0xc3), Some(2));
+        assert_eq!(sav.flag(GLOBAL_FLAGS["received_items_index_2"] as usize), Some(3));
+        assert_eq!(sav.inventory(INVENTORY["coins"]), Some(345));
+    }
+
+    #[test]
+    fn changed_flags_survive_a_write_and_read() {
+        let mut sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        sav.set_flag(0xc3, 0).unwrap();
+        sav.set_flag(0xa7, 2).unwrap();
+
+        let written_sav = Sav::from_bytes(&sav.to_bytes().unwrap()).unwrap();
+        assert_eq!(written_sav.flag(0xc3), Some(0));
+        assert_eq!(written_sav.flag(0xa7), Some(2));
+        assert_eq!(written_sav.flag(0xb6), Some(1));
+    }
+
+    #[test]
+    fn new_save_counts_received_items_from_zero() {
+        let sav = Sav::new();
+        assert_eq!(sav.flag(GLOBAL_FLAGS["received_items_counted"] as usize), Some(1));
+        assert_eq!(sav.flag(GLOBAL_FLAGS["received_items_index_2"] as usize), Some(0));
+    }
+
+    #[test]
+    fn new_save_reads_back_the_bytes_it_was_written_as() {
+        let save_bytes = Sav::new().to_bytes().unwrap();
+        assert_eq!(Sav::from_bytes(&save_bytes).unwrap().to_bytes().unwrap(), save_bytes);
+    }
+
+    #[test]
+    fn out_of_range_flags_and_slots_are_rejected() {
+        let mut sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        assert_eq!(sav.flag(4096), None);
+        assert_eq!(sav.inventory(255), None);
+        assert!(matches!(sav.set_flag(4096, 2), Err(FileGenerationError::SaveFlagOutOfRange(4096))));
+    }
+
+    #[test]
+    fn truncated_save_fails_to_read() {
+        assert!(matches!(Sav::from_bytes(&FIXTURE_SAVE[..FIXTURE_SAVE.len() - 1]), Err(FileGenerationError::SaveFileReadFailure)));
+    }
+}
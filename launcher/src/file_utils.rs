@@ -1,8 +1,9 @@
 use std::fs;
 
 use log::debug;
+use sha2::{Digest, Sha256};
 
-use crate::consts::{AP_PATH, ORIGINAL_DAT_PATH, ORIGINAL_EFFECTS_PATH, ORIGINAL_RCD_PATH};
+use crate::consts::AP_PATH;
 
 pub fn path_exists(file_path: &str, error_if_missing: bool) -> Result<bool, String> {
     match fs::exists(file_path) {
@@ -93,34 +94,36 @@ pub fn move_saves(save_source: String, save_destination: String) -> Result<(), S
     Ok(())
 }
 
-pub fn delete_seed(seed: String) -> Result<(), String> {
-    let seed_file_path = format!("{}{}/", AP_PATH, seed);
-    fs::remove_dir_all(seed_file_path.clone()).or_else(|e| {
-        let error_message = format!("File system error {} attempting to delete {}, please correct and try again.", e, seed_file_path.clone());
+pub fn delete_file(file_path: &str) -> Result<(), String> {
+    fs::remove_file(file_path).or_else(|e| {
+        let error_message = format!("File system error {} attempting to delete {}, please correct and try again.", e, file_path);
         debug!("{}", error_message);
         Err(error_message)
     })
 }
 
-pub fn update_game_files(seed: String, save_destination: String) -> Result<(), String> {
-    let seed_file_path = format!("{}{}/", AP_PATH, seed);
-
-    let rcd_path = format!("{}script.rcd", seed_file_path);
-    let _ = copy_file(&rcd_path, ORIGINAL_RCD_PATH)?;
-
-    let dat_path = format!("{}script_code.dat", seed_file_path);
-    let _ = copy_file(&dat_path, ORIGINAL_DAT_PATH)?;
-
-    let effects_path = format!("{}01effect.png", seed_file_path);
-    let _ = copy_file(&effects_path, ORIGINAL_EFFECTS_PATH)?;
-
-    if seed != "source" {
-        let app_config_path = format!("{}lamulana-config.toml", seed_file_path);
-        let _ = copy_file(&app_config_path, "./lamulana-config.toml")?;
+pub fn delete_dir(file_path: &str) -> Result<(), String> {
+    fs::remove_dir_all(file_path).or_else(|e| {
+        let error_message = format!("File system error {} attempting to delete {}, please correct and try again.", e, file_path);
+        debug!("{}", error_message);
+        Err(error_message)
+    })
+}
 
-        let save_path = format!("{}save/", seed_file_path);
-        move_saves(save_path, save_destination)?;
+// None when the file doesn't exist
+pub fn file_digest(file_path: &str) -> Result<Option<String>, String> {
+    if !path_exists(file_path, false)? {
+        return Ok(None);
     }
+    let file = read_file(file_path)?;
+    Ok(Some(hex::encode(Sha256::digest(file)).to_uppercase()))
+}
 
-    Ok(())
+pub fn delete_seed(seed: String) -> Result<(), String> {
+    let seed_file_path = format!("{}{}/", AP_PATH, seed);
+    fs::remove_dir_all(seed_file_path.clone()).or_else(|e| {
+        let error_message = format!("File system error {} attempting to delete {}, please correct and try again.", e, seed_file_path.clone());
+        debug!("{}", error_message);
+        Err(error_message)
+    })
 }
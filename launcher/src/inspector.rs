@@ -0,0 +1,97 @@
+use archipelago_api::api::SlotData;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::ap_data::APData;
+use crate::consts::{AP_PATH, APP_CONFIG_FILE_NAME};
+use crate::file_gen::app_config::{ArchipelagoItem, StoredAppConfig};
+use crate::file_gen::generator;
+use crate::file_gen::lm_consts::INVENTORY;
+use crate::file_gen::sav::Sav;
+use crate::file_utils;
+
+// The DLL counts a location as checked once its flag reaches 2, and obtaining an item sets its flag the same way
+pub const FOUND_FLAG_VALUE: u8 = 2;
+
+// Inspects every save of the seed unless a single save file is given
+pub fn inspect_seed(ap_data: &APData, seed: &str, save_file_path: Option<String>) -> Result<String, String> {
+    if ap_data.game(seed).is_none() {
+        return Err(format!("Couldn't find Seed {} while attempting to inspect it.", seed));
+    }
+    let app_config = StoredAppConfig::read(&format!("{}{}/{}", AP_PATH, seed, APP_CONFIG_FILE_NAME)).map_err(|e| e.to_string())?;
+    // Names come from the slot data, seeds generated before it was saved only show ids
+    let slot_data = generator::read_stored_slot_data(seed).ok();
+
+    let save_file_paths = match save_file_path {
+        Some(save_file_path) => vec![save_file_path],
+        None => {
+            let save_path = ap_data.seed_save_path(seed);
+            let mut save_file_paths = file_utils::read_dir(&save_path)?.filter_map(|save_file| save_file.ok())
+                .map(|save_file| save_file.file_name().to_string_lossy().to_string())
+                .filter(|file_name| file_name.starts_with("lm_") && file_name.ends_with(".sav"))
+                .map(|file_name| format!("{}{}", save_path, file_name))
+                .collect::<Vec<String>>();
+            save_file_paths.sort();
+            save_file_paths
+        }
+    };
+    if save_file_paths.is_empty() {
+        return Err(format!("Seed {} has no saves yet", seed));
+    }
+
+    let mut report = Vec::new();
+    for save_file_path in save_file_paths {
+        let save_bytes = file_utils::read_file(&save_file_path)?;
+        let sav = Sav::from_bytes(&save_bytes).map_err(|e| format!("{}: {}", save_file_path, e))?;
+        let file_name = Path::new(&save_file_path).file_name().map_or(save_file_path.clone(), |file_name| file_name.to_string_lossy().to_string());
+        report.extend(inspect_save(&file_name, &sav, &app_config.item_mapping, slot_data.as_ref()));
+    }
+    Ok(report.join("\n"))
+}
+
+fn inspect_save(file_name: &str, sav: &Sav, item_mapping: &[ArchipelagoItem], slot_data: Option<&SlotData>) -> Vec<String> {
+    let mut report = Vec::new();
+
+    let position = sav.position();
+    report.push(file_name.to_string());
+    report.push(format!("  {} (zone {} room {} screen {}, x {} y {}), HP {}", sav.zone_name(), position.zone, position.room, position.screen, position.x, position.y, sav.current_hp()));
+    report.push(format!("  Emails {}, Mantras {}/10, Maps {:?}", sav.received_emails().len(), sav.mantras_learned().len(), sav.maps_owned()));
+
+    let location_names = slot_data.map(|slot_data| {
+        slot_data.locations.iter().filter_map(|location| location.address.map(|address| (address, location.name.clone()))).collect::<HashMap<i64, String>>()
+    }).unwrap_or_default();
+    let mut checked_locations = item_mapping.iter()
+        .filter(|item| item.flag >= 0 && sav.flag(item.flag as usize) == Some(FOUND_FLAG_VALUE))
+        .map(|item| item.location_id)
+        .collect::<Vec<i64>>();
+    checked_locations.sort();
+    report.push(format!("  Checked locations ({}/{}):", checked_locations.len(), item_mapping.len()));
+    for location_id in checked_locations {
+        report.push(format!("    {} {}", location_id, location_names.get(&location_id).map_or("", |name| name.as_str())));
+    }
+
+    if let Some(slot_data) = slot_data {
+        let mut held_items = slot_data.item_table.iter()
+            .filter(|(_, item)| item.obtain_flag.is_some_and(|flag| flag >= 0 && sav.flag(flag as usize) == Some(FOUND_FLAG_VALUE)))
+            .map(|(item_name, _)| item_name.clone())
+            .collect::<Vec<String>>();
+        held_items.sort();
+        report.push(format!("  Items held ({}):", held_items.len()));
+        for item_name in held_items {
+            report.push(format!("    {}", item_name));
+        }
+    }
+
+    // Coins, weights and ammo have no obtain flag, only a quantity
+    let mut quantities = INVENTORY.iter()
+        .filter(|(_, inventory_slot)| **inventory_slot >= INVENTORY["weights"])
+        .filter_map(|(name, inventory_slot)| sav.inventory(*inventory_slot).map(|quantity| (*inventory_slot, name.replace('_', " "), quantity)))
+        .filter(|(_, _, quantity)| *quantity > 0)
+        .collect::<Vec<(usize, String, u16)>>();
+    quantities.sort();
+    for (_, name, quantity) in quantities {
+        report.push(format!("  {} {}", quantity, name));
+    }
+
+    report
+}
@@ -2,22 +2,30 @@
 
 pub mod ap_connection;
 pub mod ap_data;
+pub mod bundle;
+pub mod cli;
 pub mod consts;
 pub mod file_gen;
 pub mod file_utils;
+pub mod inspector;
+pub mod migration;
+pub mod rotation;
+pub mod snapshots;
 pub mod verifier;
+pub mod versions;
 
 use archipelago_api::api::*;
 use dll_syringe::{process::OwnedProcess, Syringe};
 use log::{debug, LevelFilter};
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Logger, Root};
-use slint::{ComponentHandle, ModelRc, VecModel, Weak};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel, Weak};
 use std::error::Error;
 use std::sync::Mutex;
 use std::process;
 use std::rc::Rc;
 use thiserror::Error;
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
 
 use crate::ap_connection::APConnection;
 use crate::ap_data::{APData, Game, Player};
@@ -42,12 +50,32 @@ pub enum NewSeedError {
     InvalidPacket,
     #[error("Archipelago failed to send slot data, please confirm lamulana APworld is up to date")]
     SlotDataMissing,
+    #[error("Archipelago sent slot data the launcher can't read, please confirm all software is up to date")]
+    SlotDataMalformed,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     configure_logger().await;
 
+    // Any arguments run a single command without opening a window
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        // Release builds use the windows subsystem, so output only reaches the terminal once attached to it.
+        // Fails harmlessly in debug builds, which already have a console
+        unsafe { AttachConsole(ATTACH_PARENT_PROCESS); }
+        let result = match cli::parse_args(&args) {
+            Ok(command) => cli::run(command).await,
+            Err(usage) => Err(usage)
+        };
+        if let Err(error_message) = result {
+            debug!("Command {:?} failed: {}", args, error_message);
+            eprintln!("{}", error_message);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
     match verifier::verify_install() {
         Ok(lm_config) => {
             let ap_data = APData::new(lm_config)?;
@@ -87,7 +115,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 async fn configure_logger() {
     let file_appender = FileAppender::builder()
-        .build("lmmw_launcher.txt")
+        .build(LAUNCHER_LOG_PATH)
         .unwrap();
     let log_config = Config::builder()
         .appender(Appender::builder().build("lmmw_launcher", Box::new(file_appender)))
@@ -177,6 +205,13 @@ async fn configure_seed_selector_window(seed_selector_handle: Weak<SeedSelector>
     let seed_selector_close_handle = seed_selector_handle.clone();
     let seed_selector_load_handle = seed_selector_handle.clone();
     let seed_selector_delete_handle = seed_selector_handle.clone();
+    let seed_selector_regenerate_handle = seed_selector_handle.clone();
+    let seed_selector_edit_handle = seed_selector_handle.clone();
+    let seed_selector_save_connection_handle = seed_selector_handle.clone();
+    let seed_selector_export_handle = seed_selector_handle.clone();
+    let seed_selector_import_handle = seed_selector_handle.clone();
+    let seed_selector_show_snapshots_handle = seed_selector_handle.clone();
+    let seed_selector_restore_snapshot_handle = seed_selector_handle.clone();
     let seed_selector_add_seed_handle = seed_selector_handle.clone();
 
     let launcher_close = launcher_handle.clone().unwrap();
@@ -236,6 +271,255 @@ async fn configure_seed_selector_window(seed_selector_handle: Weak<SeedSelector>
         seed_selector.set_load_seed_error(seed_error_message.into());
     });
 
+    seed_selector.on_regenerate(move || {
+        let seed_selector = seed_selector_regenerate_handle.clone().unwrap();
+
+        let mut seed_error_message = "".to_string();
+        let seed_to_regenerate = seed_selector.get_chosen_seed().to_string();
+
+        match AP_DATA.lock() {
+            Ok(ap_data_lock) => {
+                match ap_data_lock.as_ref() {
+                    Some(ap_data) => {
+                        match ap_data.regenerate_game(seed_to_regenerate.clone()) {
+                            Ok(report) => {
+                                // Regenerating succeeded, but the save may not line up with the new files
+                                seed_error_message = report.warnings().join("\n");
+                            },
+                            Err(e) => {
+                                seed_error_message = format!("Failed to Regenerate Chosen Seed: {}", e);
+                                debug!("{}", seed_error_message);
+                            }
+                        }
+                    },
+                    None => {
+                        seed_error_message = "AP Data doesn't exist".to_string();
+                        debug!("{}", seed_error_message);
+                    }
+                }
+            },
+            Err(e) => {
+                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                debug!("{}: {:?}", seed_error_message, e);
+            }
+        }
+
+        seed_selector.set_load_seed_error(seed_error_message.into());
+    });
+
+    seed_selector.on_edit(move || {
+        let seed_selector = seed_selector_edit_handle.clone().unwrap();
+
+        let mut seed_error_message = "".to_string();
+        let seed_to_edit = seed_selector.get_chosen_seed().to_string();
+
+        match AP_DATA.lock() {
+            Ok(ap_data_lock) => {
+                match ap_data_lock.as_ref().and_then(|ap_data| ap_data.game(&seed_to_edit)) {
+                    Some(game) => {
+                        seed_selector.set_server_url(game.server_url.clone().into());
+                        seed_selector.set_password(game.password.clone().into());
+                        seed_selector.set_player_name(game.you.name.clone().into());
+                        seed_selector.set_add_seed_error("".into());
+                        seed_selector.set_editing(true);
+                    },
+                    None => {
+                        seed_error_message = format!("Couldn't find Seed {}", seed_to_edit);
+                        debug!("{}", seed_error_message);
+                    }
+                }
+            },
+            Err(e) => {
+                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                debug!("{}: {:?}", seed_error_message, e);
+            }
+        }
+
+        seed_selector.set_load_seed_error(seed_error_message.into());
+    });
+
+    seed_selector.on_save_connection(move || {
+        let seed_selector = seed_selector_save_connection_handle.clone().unwrap();
+
+        let seed_to_edit = seed_selector.get_chosen_seed().to_string();
+        let server_url = seed_selector.get_server_url().to_string();
+        let password = seed_selector.get_password().to_string();
+        let player_name = seed_selector.get_player_name().to_string();
+        let verify_connection = seed_selector.get_verify_connection();
+
+        let seed_selector_text_handle = seed_selector_save_connection_handle.clone();
+
+        let _ = slint::spawn_local(async move {
+            let _ = tokio::spawn(async move {
+                let mut seed_error_message = "".to_string();
+                let verified = if verify_connection {
+                    verify_existing_seed(&seed_to_edit, server_url.clone(), password.clone(), player_name.clone()).await
+                } else {
+                    Ok(())
+                };
+
+                match verified {
+                    Ok(_) => {
+                        match AP_DATA.lock() {
+                            Ok(mut ap_data_lock) => {
+                                match ap_data_lock.as_mut() {
+                                    Some(ap_data) => {
+                                        if let Err(e) = ap_data.update_connection(seed_to_edit.clone(), server_url.clone(), password.clone(), player_name.clone()) {
+                                            seed_error_message = "Failed to Update Seed Connection".to_string();
+                                            debug!("{}: {:?}", seed_error_message, e);
+                                        }
+                                    },
+                                    None => {
+                                        seed_error_message = "AP Data doesn't exist".to_string();
+                                        debug!("{}", seed_error_message);
+                                    }
+                                }
+                            },
+                            Err(e) => {
+                                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                                debug!("{}: {:?}", seed_error_message, e);
+                            }
+                        }
+                    },
+                    Err(e) => {
+                        seed_error_message = e;
+                        debug!("Seed Connection Failed to Validate: {}", seed_error_message);
+                    }
+                }
+
+                let _ = seed_selector_text_handle.upgrade_in_event_loop(move |seed_selector| {
+                    if seed_error_message.is_empty() {
+                        seed_selector.set_editing(false);
+                        seed_selector.set_server_url("".into());
+                        seed_selector.set_password("".into());
+                        seed_selector.set_player_name("".into());
+                    }
+                    seed_selector.set_add_seed_error(seed_error_message.into());
+                }).unwrap();
+            }).await.unwrap();
+        });
+    });
+
+    seed_selector.on_export(move || {
+        let seed_selector = seed_selector_export_handle.clone().unwrap();
+
+        let seed_error_message;
+        let seed_to_export = seed_selector.get_chosen_seed().to_string();
+
+        match AP_DATA.lock() {
+            Ok(ap_data_lock) => {
+                match ap_data_lock.as_ref() {
+                    Some(ap_data) => {
+                        match bundle::export_seed(ap_data, &seed_to_export, None) {
+                            Ok(bundle_path) => {
+                                seed_error_message = format!("Exported to {}", bundle_path);
+                            },
+                            Err(e) => {
+                                seed_error_message = format!("Failed to Export Chosen Seed: {}", e);
+                                debug!("{}", seed_error_message);
+                            }
+                        }
+                    },
+                    None => {
+                        seed_error_message = "AP Data doesn't exist".to_string();
+                        debug!("{}", seed_error_message);
+                    }
+                }
+            },
+            Err(e) => {
+                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                debug!("{}: {:?}", seed_error_message, e);
+            }
+        }
+
+        seed_selector.set_load_seed_error(seed_error_message.into());
+    });
+
+    seed_selector.on_import_bundle(move || {
+        let seed_selector = seed_selector_import_handle.clone().unwrap();
+
+        let mut seed_error_message = "".to_string();
+        let bundle_path = seed_selector.get_bundle_path().to_string();
+
+        match AP_DATA.lock() {
+            Ok(mut ap_data_lock) => {
+                match ap_data_lock.as_mut() {
+                    Some(ap_data) => {
+                        match bundle::import_seed(ap_data, &bundle_path) {
+                            Ok(imported_bundle) => {
+                                if imported_bundle.password_redacted {
+                                    seed_error_message = format!("Imported {}, its password was removed, use Edit to enter it", imported_bundle.seed);
+                                }
+                                let seeds = Rc::new(VecModel::from(ap_data.seeds()));
+                                seed_selector.set_seeds(ModelRc::from(seeds));
+                                seed_selector.set_bundle_path("".into());
+                            },
+                            Err(e) => {
+                                seed_error_message = format!("Failed to Import Bundle: {}", e);
+                                debug!("{}", seed_error_message);
+                            }
+                        }
+                    },
+                    None => {
+                        seed_error_message = "AP Data doesn't exist".to_string();
+                        debug!("{}", seed_error_message);
+                    }
+                }
+            },
+            Err(e) => {
+                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                debug!("{}: {:?}", seed_error_message, e);
+            }
+        }
+
+        seed_selector.set_add_seed_error(seed_error_message.into());
+    });
+
+    seed_selector.on_show_snapshots(move || {
+        let seed_selector = seed_selector_show_snapshots_handle.clone().unwrap();
+        let seed = seed_selector.get_chosen_seed().to_string();
+        let seed_error_message = show_snapshots(&seed_selector, &seed).err().unwrap_or_default();
+        seed_selector.set_load_seed_error(seed_error_message.into());
+    });
+
+    seed_selector.on_restore_snapshot(move || {
+        let seed_selector = seed_selector_restore_snapshot_handle.clone().unwrap();
+
+        let seed_error_message;
+        let seed = seed_selector.get_snapshot_seed().to_string();
+        let snapshot_name = seed_selector.get_snapshot_names().row_data(seed_selector.get_chosen_snapshot() as usize).unwrap_or_default().to_string();
+
+        match AP_DATA.lock() {
+            Ok(ap_data_lock) => {
+                match ap_data_lock.as_ref() {
+                    Some(ap_data) => {
+                        match snapshots::restore_snapshot(ap_data, &seed, &snapshot_name) {
+                            Ok(_) => {
+                                seed_error_message = format!("Restored snapshot {} of {}", snapshot_name, seed);
+                            },
+                            Err(e) => {
+                                seed_error_message = format!("Failed to Restore Snapshot: {}", e);
+                                debug!("{}", seed_error_message);
+                            }
+                        }
+                    },
+                    None => {
+                        seed_error_message = "AP Data doesn't exist".to_string();
+                        debug!("{}", seed_error_message);
+                    }
+                }
+            },
+            Err(e) => {
+                seed_error_message = "Failed To Acquire AP Lock".to_string();
+                debug!("{}: {:?}", seed_error_message, e);
+            }
+        }
+
+        // Restoring takes a snapshot of its own, so the list is refreshed
+        let _ = show_snapshots(&seed_selector, &seed);
+        seed_selector.set_load_seed_error(seed_error_message.into());
+    });
+
     seed_selector.on_load(move || {
         let seed_selector = seed_selector_load_handle.clone().unwrap();
 
@@ -291,10 +575,10 @@ async fn configure_seed_selector_window(seed_selector_handle: Weak<SeedSelector>
             let _ = tokio::spawn(async move {
                 let mut seed_error_message = "".to_string();
                 match verify_new_seed(server_url.clone(), password.clone(), player_name.clone()).await {
-                    Ok(slot_data) => {
+                    Ok((slot_data, slot_data_bytes)) => {
                         let app_config = AppConfig::new(server_url.clone(), password.clone(), slot_data.player_id.clone(), slot_data.players.clone());
                         let local_seed_name = format!("{}-{}", slot_data.seed.clone(), slot_data.player_id.clone());
-                        match generator::generate_files(app_config, slot_data.clone(), local_seed_name.clone()) {
+                        match generator::generate_files(app_config, slot_data_bytes, local_seed_name.clone(), true) {
                             Ok(_) => {
                                 let game = Game {
                                     seed: local_seed_name.clone(),
@@ -367,14 +651,32 @@ async fn configure_seed_selector_window(seed_selector_handle: Weak<SeedSelector>
     });
 }
 
-async fn verify_new_seed(server_url: String, password: String, player_name: String) -> Result<SlotData, NewSeedError> {
+fn show_snapshots(seed_selector: &SeedSelector, seed: &str) -> Result<(), String> {
+    let snapshots = snapshots::list_snapshots(seed).map_err(|e| format!("Failed to List Snapshots: {}", e))?;
+    if snapshots.is_empty() {
+        return Err(format!("Seed {} has no snapshots yet", seed));
+    }
+
+    let labels = snapshots.iter().map(|snapshot| snapshot.label().into()).collect::<Vec<SharedString>>();
+    let names = snapshots.iter().map(|snapshot| snapshot.name.clone().into()).collect::<Vec<SharedString>>();
+    seed_selector.set_snapshots(ModelRc::from(Rc::new(VecModel::from(labels))));
+    seed_selector.set_snapshot_names(ModelRc::from(Rc::new(VecModel::from(names))));
+    seed_selector.set_chosen_snapshot(0);
+    seed_selector.set_snapshot_seed(seed.into());
+    Ok(())
+}
+
+// Returns the slot data along with the bytes it is stored as, which are kept exactly as the server sent them
+async fn verify_new_seed(server_url: String, password: String, player_name: String) -> Result<(SlotData, Vec<u8>), NewSeedError> {
     let ap_connection = APConnection::new();
     let mut ap_client = ap_connection.connect_to_archipelago(player_name, server_url, password).await.map_err(|_| NewSeedError::ConnectionFailure)?;
     loop {
         let payload = ap_client.read().await.map_err(|_| NewSeedError::ConnectionDropped)?;
         match payload {
             ServerPayload::Connected(connected) => {
-                return connected.slot_data.ok_or(NewSeedError::SlotDataMissing);
+                let slot_data_bytes = serde_json::to_vec(&connected.slot_data.ok_or(NewSeedError::SlotDataMissing)?).map_err(|_| NewSeedError::SlotDataMalformed)?;
+                let slot_data = generator::parse_slot_data(&slot_data_bytes).map_err(|_| NewSeedError::SlotDataMalformed)?;
+                return Ok((slot_data, slot_data_bytes));
             },
             ServerPayload::ConnectionRefused(connection_refused) => {
                 debug!("Connection Refused: {:?}", connection_refused);
@@ -389,16 +691,31 @@ async fn verify_new_seed(server_url: String, password: String, player_name: Stri
     }
 }
 
+// The room must still be hosting the same seed, and the name must still belong to the seed's slot
+async fn verify_existing_seed(seed: &str, server_url: String, password: String, player_name: String) -> Result<(), String> {
+    let (slot_data, _) = verify_new_seed(server_url, password, player_name).await.map_err(|e| e.to_string())?;
+    let connected_seed = format!("{}-{}", slot_data.seed, slot_data.player_id);
+    if connected_seed != seed {
+        return Err(format!("Server is hosting seed {}, not {}", connected_seed, seed));
+    }
+    Ok(())
+}
+
 async fn launch_game() {
+    // The command line keeps its own AP Data and snapshots before calling this
+    if let Ok(ap_data_lock) = AP_DATA.lock() {
+        if let Some(ap_data) = ap_data_lock.as_ref() {
+            snapshots::take_launch_snapshot(ap_data);
+        }
+    }
+
     match process::Command::new(LAMULANA_EXECUTABLE_NAME).spawn() {
         Ok(mut p) => {
-            let dll = "LaMulanaMW.dll";
-
             let process_id = p.id();
             let target_process = OwnedProcess::from_pid(process_id).unwrap();
             let syringe = Syringe::for_process(target_process);
 
-            match syringe.inject(dll) {
+            match syringe.inject(DLL_NAME) {
                 Ok(_) => {
                     p.wait().unwrap();
                 },
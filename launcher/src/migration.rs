@@ -0,0 +1,141 @@
+use log::debug;
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::ap_data::APData;
+use crate::consts::{AP_PATH, APP_CONFIG_FILE_NAME, PREVIOUS_APP_CONFIG_FILE_NAME};
+use crate::file_gen::app_config::{ArchipelagoItem, StoredAppConfig};
+use crate::file_gen::generator::FileGenerationError;
+use crate::file_gen::lm_consts::GLOBAL_FLAGS;
+use crate::file_gen::sav::Sav;
+use crate::file_utils;
+use crate::inspector::FOUND_FLAG_VALUE;
+use crate::snapshots;
+
+pub struct MigrationReport {
+    pub output_path: String,
+    pub checked_locations: usize,
+    // Checked in the old save, but missing from the seed's current item mapping
+    pub dropped_locations: Vec<i64>
+}
+
+#[derive(Debug)]
+pub struct MigrationOptions {
+    pub old_save_path: String,
+    // Defaults to the config kept when the seed was last regenerated
+    pub old_app_config_path: Option<String>,
+    // Defaults to the save of the same name in the seed's save directory, which is snapshotted first
+    pub output_path: Option<String>
+}
+
+// Everything outside the item mapping is kept as is, including inventory, HP, position, emails and the received item index
+pub fn migrate_save(ap_data: &APData, seed: &str, options: MigrationOptions) -> Result<MigrationReport, String> {
+    if ap_data.game(seed).is_none() {
+        return Err(format!("Couldn't find Seed {} while attempting to migrate a save to it.", seed));
+    }
+
+    let seed_path = format!("{}{}/", AP_PATH, seed);
+    let old_app_config_path = options.old_app_config_path.unwrap_or_else(|| format!("{}{}", seed_path, PREVIOUS_APP_CONFIG_FILE_NAME));
+    let old_app_config = StoredAppConfig::read(&old_app_config_path).map_err(|e| format!("{}: {}", old_app_config_path, e))?;
+    let new_app_config = StoredAppConfig::read(&format!("{}{}", seed_path, APP_CONFIG_FILE_NAME)).map_err(|e| e.to_string())?;
+
+    let old_save_bytes = file_utils::read_file(&options.old_save_path)?;
+    let old_sav = Sav::from_bytes(&old_save_bytes).map_err(|e| format!("{}: {}", options.old_save_path, e))?;
+    let mut new_sav = Sav::from_bytes(&old_save_bytes).map_err(|e| format!("{}: {}", options.old_save_path, e))?;
+
+    let dropped_locations = remap_item_flags(&old_sav, &mut new_sav, &old_app_config.item_mapping, &new_app_config.item_mapping).map_err(|e| e.to_string())?;
+
+    // Not part of the item mapping, but written explicitly since delivery would repeat or skip items if it were lost
+    for received_items_flag in ["received_items_index_1", "received_items_index_2", "received_items_counted"] {
+        let flag = GLOBAL_FLAGS[received_items_flag] as usize;
+        new_sav.set_flag(flag, old_sav.flag(flag).unwrap_or(0)).map_err(|e| e.to_string())?;
+    }
+
+    let output_path = match options.output_path {
+        Some(output_path) => output_path,
+        None => {
+            let file_name = Path::new(&options.old_save_path).file_name().ok_or_else(|| format!("{} is not a save file", options.old_save_path))?;
+            snapshots::take_snapshot(ap_data, seed, "migrate")?;
+            format!("{}{}", ap_data.seed_save_path(seed), file_name.to_string_lossy())
+        }
+    };
+    let new_save_bytes = new_sav.to_bytes().map_err(|e| e.to_string())?;
+    file_utils::write_file(&output_path, new_save_bytes)?;
+
+    let checked_locations = new_app_config.item_mapping.iter().filter(|item| item.flag >= 0 && new_sav.flag(item.flag as usize) == Some(FOUND_FLAG_VALUE)).count();
+    debug!("Migrated {} to {} with {} checked locations", options.old_save_path, output_path, checked_locations);
+    Ok(MigrationReport { output_path, checked_locations, dropped_locations })
+}
+
+// Moves each location's flag value from its old flag to its new one, returning the checked locations that have nowhere to go.
+// Old flags past the end of the save read as unchecked, new ones fail the migration
+fn remap_item_flags(old_sav: &Sav, new_sav: &mut Sav, old_mapping: &[ArchipelagoItem], new_mapping: &[ArchipelagoItem]) -> Result<Vec<i64>, FileGenerationError> {
+    let old_values = old_mapping.iter()
+        .filter(|item| item.flag >= 0)
+        .filter_map(|item| old_sav.flag(item.flag as usize).map(|value| (item.location_id, value)))
+        .collect::<HashMap<i64, u8>>();
+    let old_flags = old_mapping.iter().map(|item| item.flag).collect::<HashSet<i16>>();
+    let new_flags = new_mapping.iter().map(|item| item.flag).collect::<HashSet<i16>>();
+
+    // A flag that belonged to another location in the old layout starts cleared, any other flag keeps its value
+    let mut new_values: HashMap<i16, u8> = HashMap::new();
+    for item in new_mapping.iter().filter(|item| item.flag >= 0) {
+        let value = match old_values.get(&item.location_id) {
+            Some(old_value) => *old_value,
+            None if old_flags.contains(&item.flag) => 0,
+            None => old_sav.flag(item.flag as usize).unwrap_or(0)
+        };
+        let new_value = new_values.entry(item.flag).or_insert(0);
+        *new_value = max(*new_value, value);
+    }
+
+    for flag in old_flags.difference(&new_flags).filter(|flag| **flag >= 0) {
+        new_sav.set_flag(*flag as usize, 0)?;
+    }
+    for (flag, value) in new_values {
+        new_sav.set_flag(flag as usize, value)?;
+    }
+
+    let new_locations = new_mapping.iter().map(|item| item.location_id).collect::<HashSet<i64>>();
+    let mut dropped_locations = old_values.into_iter()
+        .filter(|(location_id, value)| *value > 0 && !new_locations.contains(location_id))
+        .map(|(location_id, _)| location_id)
+        .collect::<Vec<i64>>();
+    dropped_locations.sort();
+    Ok(dropped_locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE_SAVE: &[u8] = include_bytes!("../fixtures/lm_00.sav");
+
+    fn item(location_id: i64, flag: i16) -> ArchipelagoItem {
+        ArchipelagoItem { flag, location_id, player_id: 1, obtain_value: 2 }
+    }
+
+    #[test]
+    fn remap_item_flags_moves_checks_to_their_new_flags() {
+        let old_sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        let mut new_sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        let old_mapping = [item(2359000, 0xc3), item(2359005, 0xa7), item(2359010, 0xc7)];
+        let new_mapping = [item(2359000, 0xa7), item(2359005, 0xc3)];
+
+        let dropped_locations = remap_item_flags(&old_sav, &mut new_sav, &old_mapping, &new_mapping).unwrap();
+        assert_eq!(new_sav.flag(0xa7), Some(2));
+        assert_eq!(new_sav.flag(0xc3), Some(0));
+        assert!(dropped_locations.is_empty());
+    }
+
+    #[test]
+    fn remap_item_flags_fails_on_flags_past_the_end_of_the_save() {
+        let old_sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        let mut new_sav = Sav::from_bytes(FIXTURE_SAVE).unwrap();
+        let old_mapping = [item(2359000, 0xc3)];
+        let new_mapping = [item(2359000, 0x1000)];
+
+        assert!(matches!(remap_item_flags(&old_sav, &mut new_sav, &old_mapping, &new_mapping), Err(FileGenerationError::SaveFlagOutOfRange(0x1000))));
+    }
+}
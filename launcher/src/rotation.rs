@@ -0,0 +1,181 @@
+use log::{debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::consts::{AP_PATH, APP_CONFIG_FILE_NAME, ORIGINAL_DAT_PATH, ORIGINAL_EFFECTS_PATH, ORIGINAL_RCD_PATH, ROTATION_BACKUP_PATH, ROTATION_JOURNAL_PATH};
+use crate::file_utils;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct FileCopy {
+    source: String,
+    destination: String,
+    digest: String,
+    // Where the destination's previous contents were kept, None if it didn't exist yet
+    backup: Option<String>
+}
+
+// Written before anything in the install is touched, and removed once every copy is verified
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RotationJournal {
+    seed: String,
+    copies: Vec<FileCopy>
+}
+
+pub enum Recovery {
+    RolledForward(String),
+    RolledBack(String)
+}
+
+// Swaps the install over to a seed's files, saving the previous seed's saves back to its directory first
+pub fn rotate(previous_seed: Option<String>, seed: String, save_path: String) -> Result<(), String> {
+    let journal = RotationJournal { seed: seed.clone(), copies: back_up(plan_copies(previous_seed, &seed, &save_path)?)? };
+    write_journal(&journal)?;
+
+    if let Err(e) = apply(&journal) {
+        warn!("Rotation to {} failed, rolling back: {}", seed, e);
+        roll_back(&journal)?;
+        finish()?;
+        return Err(e);
+    }
+    finish()
+}
+
+// Called on startup, a journal left behind means the launcher stopped partway through a rotation
+pub fn recover() -> Result<Option<Recovery>, String> {
+    if !file_utils::path_exists(&ROTATION_JOURNAL_PATH, false)? {
+        // Backups made before the journal was written are never needed
+        if file_utils::path_exists(&ROTATION_BACKUP_PATH, false)? {
+            file_utils::delete_dir(&ROTATION_BACKUP_PATH)?;
+        }
+        return Ok(None);
+    }
+
+    let serialized_journal = file_utils::read_file_as_string(&ROTATION_JOURNAL_PATH)?;
+    let journal = serde_json::from_str::<RotationJournal>(&serialized_journal).map_err(|e| {
+        format!("Error {} while attempting to deserialize the rotation journal {}, please restore the original files.", e, *ROTATION_JOURNAL_PATH)
+    })?;
+
+    let recovery = match apply(&journal) {
+        Ok(_) => {
+            debug!("Rolled forward an incomplete rotation to {}", journal.seed);
+            Recovery::RolledForward(journal.seed.clone())
+        },
+        Err(e) => {
+            warn!("Rolling back an incomplete rotation to {}: {}", journal.seed, e);
+            roll_back(&journal)?;
+            Recovery::RolledBack(journal.seed.clone())
+        }
+    };
+    finish()?;
+    Ok(Some(recovery))
+}
+
+fn plan_copies(previous_seed: Option<String>, seed: &str, save_path: &str) -> Result<Vec<(String, String)>, String> {
+    let mut copies = Vec::new();
+
+    if let Some(previous_seed) = previous_seed {
+        let previous_save_path = format!("{}{}/save/", AP_PATH, previous_seed);
+        copies.extend(save_files(save_path)?.into_iter().map(|save_file_name| {
+            (format!("{}{}", save_path, save_file_name), format!("{}{}", previous_save_path, save_file_name))
+        }));
+    }
+
+    let seed_file_path = format!("{}{}/", AP_PATH, seed);
+    copies.push((format!("{}script.rcd", seed_file_path), ORIGINAL_RCD_PATH.to_string()));
+    copies.push((format!("{}script_code.dat", seed_file_path), ORIGINAL_DAT_PATH.to_string()));
+    copies.push((format!("{}01effect.png", seed_file_path), ORIGINAL_EFFECTS_PATH.to_string()));
+
+    if seed != "source" {
+        copies.push((format!("{}{}", seed_file_path, APP_CONFIG_FILE_NAME), format!("./{}", APP_CONFIG_FILE_NAME)));
+
+        let seed_save_path = format!("{}save/", seed_file_path);
+        copies.extend(save_files(&seed_save_path)?.into_iter().map(|save_file_name| {
+            (format!("{}{}", seed_save_path, save_file_name), format!("{}{}", save_path, save_file_name))
+        }));
+    }
+
+    Ok(copies)
+}
+
+fn save_files(save_path: &str) -> Result<Vec<String>, String> {
+    if !file_utils::path_exists(save_path, false)? {
+        return Ok(Vec::new());
+    }
+    let save_dir = file_utils::read_dir(save_path)?;
+    Ok(save_dir.filter_map(|save_file| save_file.ok())
+        .filter(|save_file| save_file.path().is_file())
+        .map(|save_file| save_file.file_name().to_string_lossy().to_string())
+        .collect())
+}
+
+// Digests are taken up front, so a copy can later be checked against what the source held when the rotation began
+fn back_up(copies: Vec<(String, String)>) -> Result<Vec<FileCopy>, String> {
+    file_utils::create_dir(&ROTATION_BACKUP_PATH)?;
+    // Reloading the active seed copies its saves out and straight back in, so a source may be written earlier in the same rotation
+    let mut planned_digests: HashMap<String, String> = HashMap::new();
+    copies.into_iter().enumerate().map(|(index, (source, destination))| {
+        let digest = match planned_digests.get(&source) {
+            Some(digest) => digest.clone(),
+            None => file_utils::file_digest(&source)?.ok_or_else(|| {
+                let error_message = format!("{} is missing, please correct and try again.", source);
+                debug!("{}", error_message);
+                error_message
+            })?
+        };
+        planned_digests.insert(destination.clone(), digest.clone());
+        let backup = match file_utils::path_exists(&destination, false)? {
+            true => {
+                let backup_path = format!("{}{}", ROTATION_BACKUP_PATH.to_string(), index);
+                file_utils::copy_file(&destination, &backup_path)?;
+                Some(backup_path)
+            },
+            false => None
+        };
+        Ok(FileCopy { source, destination, digest, backup })
+    }).collect()
+}
+
+// Safe to repeat, copies whose destination already matches are skipped
+fn apply(journal: &RotationJournal) -> Result<(), String> {
+    for copy in &journal.copies {
+        if file_utils::file_digest(&copy.destination)?.as_ref() == Some(&copy.digest) {
+            continue;
+        }
+        if file_utils::file_digest(&copy.source)?.as_ref() != Some(&copy.digest) {
+            return Err(format!("{} changed during the rotation", copy.source));
+        }
+        file_utils::copy_file(&copy.source, &copy.destination)?;
+        if file_utils::file_digest(&copy.destination)?.as_ref() != Some(&copy.digest) {
+            return Err(format!("{} doesn't match {} after copying", copy.destination, copy.source));
+        }
+    }
+    Ok(())
+}
+
+fn roll_back(journal: &RotationJournal) -> Result<(), String> {
+    for copy in journal.copies.iter().rev() {
+        match &copy.backup {
+            Some(backup) => {
+                file_utils::copy_file(backup, &copy.destination)?;
+            },
+            None => {
+                if file_utils::path_exists(&copy.destination, false)? {
+                    file_utils::delete_file(&copy.destination)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_journal(journal: &RotationJournal) -> Result<(), String> {
+    let serialized_journal = serde_json::to_string(journal).map_err(|e| {
+        format!("Error {} while attempting to serialize the rotation journal.", e)
+    })?;
+    file_utils::write_file(&ROTATION_JOURNAL_PATH, serialized_journal)
+}
+
+fn finish() -> Result<(), String> {
+    file_utils::delete_file(&ROTATION_JOURNAL_PATH)?;
+    file_utils::delete_dir(&ROTATION_BACKUP_PATH)
+}
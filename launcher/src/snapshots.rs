@@ -0,0 +1,173 @@
+use log::debug;
+use serde::{Serialize, Deserialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ap_data::APData;
+use crate::consts::{AP_PATH, SNAPSHOT_LIMIT, SNAPSHOT_METADATA_FILE_NAME};
+use crate::file_gen::sav::Sav;
+use crate::file_utils;
+
+pub struct Snapshot {
+    pub name: String,
+    pub taken_at: u64,
+    pub reason: String,
+    pub saves: Vec<SaveSummary>
+}
+
+// Kept next to the saves, the directory name is only there to make snapshots easy to find by hand
+#[derive(Serialize, Deserialize)]
+struct SnapshotMetadata {
+    reason: String,
+    taken_at: u64
+}
+
+pub struct SaveSummary {
+    pub file_name: String,
+    // None when the save couldn't be parsed
+    pub details: Option<SaveDetails>
+}
+
+pub struct SaveDetails {
+    pub zone_name: String,
+    pub current_hp: u16
+}
+
+impl Snapshot {
+    pub fn label(&self) -> String {
+        let saves = self.saves.iter().map(|save| {
+            match &save.details {
+                Some(details) => format!("{} {} HP {}", save.file_name, details.zone_name, details.current_hp),
+                None => format!("{} unreadable", save.file_name)
+            }
+        }).collect::<Vec<String>>();
+        format!("{} ({}) {}", format_timestamp(self.taken_at), self.reason, saves.join(", "))
+    }
+}
+
+// Snapshots live with the seed, so deleting or exporting a seed takes its history along
+fn snapshots_path(seed: &str) -> String {
+    format!("{}{}/snapshots/", AP_PATH, seed)
+}
+
+pub fn take_snapshot(ap_data: &APData, seed: &str, reason: &str) -> Result<(), String> {
+    let save_path = ap_data.seed_save_path(seed);
+    let save_files = save_files(&save_path)?;
+    if save_files.is_empty() {
+        return Ok(());
+    }
+
+    let taken_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+    let mut snapshot_path = format!("{}{}-{}/", snapshots_path(seed), taken_at, reason);
+    // Two snapshots within the same second, such as a swap straight after a launch
+    let mut duplicate = 1;
+    while file_utils::path_exists(&snapshot_path, false)? {
+        duplicate += 1;
+        snapshot_path = format!("{}{}-{}-{}/", snapshots_path(seed), taken_at, reason, duplicate);
+    }
+
+    file_utils::create_dir(&snapshot_path)?;
+    let metadata = SnapshotMetadata { reason: reason.to_string(), taken_at };
+    let serialized_metadata = serde_json::to_string(&metadata).map_err(|e| format!("Error {} while attempting to serialize snapshot metadata", e))?;
+    file_utils::write_file(&format!("{}{}", snapshot_path, SNAPSHOT_METADATA_FILE_NAME), serialized_metadata)?;
+    for save_file in save_files {
+        file_utils::copy_file(&format!("{}{}", save_path, save_file), &format!("{}{}", snapshot_path, save_file))?;
+    }
+    debug!("Took {} snapshot of Seed {} at {}", reason, seed, snapshot_path);
+
+    prune_snapshots(seed)
+}
+
+// A failed snapshot shouldn't keep the game from launching
+pub fn take_launch_snapshot(ap_data: &APData) {
+    if let Some(active_game) = &ap_data.active_game {
+        if let Err(e) = take_snapshot(ap_data, &active_game.seed, "launch") {
+            debug!("Failed to snapshot saves for {}: {}", active_game.seed, e);
+        }
+    }
+}
+
+// Newest first
+pub fn list_snapshots(seed: &str) -> Result<Vec<Snapshot>, String> {
+    let mut snapshots = snapshot_names(seed)?.into_iter().filter_map(|name| {
+        let snapshot_path = format!("{}{}/", snapshots_path(seed), name);
+        let serialized_metadata = file_utils::read_file_as_string(&format!("{}{}", snapshot_path, SNAPSHOT_METADATA_FILE_NAME)).ok()?;
+        let SnapshotMetadata { reason, taken_at } = serde_json::from_str::<SnapshotMetadata>(&serialized_metadata).ok()?;
+        let saves = save_files(&snapshot_path).ok()?.into_iter().map(|file_name| {
+            let details = file_utils::read_file(&format!("{}{}", snapshot_path, file_name)).ok()
+                .and_then(|save_bytes| Sav::from_bytes(&save_bytes).ok())
+                .map(|sav| SaveDetails { zone_name: sav.zone_name(), current_hp: sav.current_hp() });
+            SaveSummary { file_name, details }
+        }).collect();
+        Some(Snapshot { name, taken_at, reason, saves })
+    }).collect::<Vec<Snapshot>>();
+    snapshots.sort_by(|a, b| b.taken_at.cmp(&a.taken_at).then_with(|| b.name.cmp(&a.name)));
+    Ok(snapshots)
+}
+
+// The current saves are snapshotted first, so a restore can itself be undone
+pub fn restore_snapshot(ap_data: &APData, seed: &str, snapshot_name: &str) -> Result<(), String> {
+    let snapshot_path = format!("{}{}/", snapshots_path(seed), snapshot_name);
+    if snapshot_name.is_empty() || snapshot_name.contains(['/', '\\', '.']) || !file_utils::path_exists(&snapshot_path, false)? {
+        return Err(format!("Couldn't find snapshot {} for Seed {}", snapshot_name, seed));
+    }
+
+    take_snapshot(ap_data, seed, "restore")?;
+
+    let save_path = ap_data.seed_save_path(seed);
+    file_utils::create_dir(&save_path)?;
+    for save_file in save_files(&snapshot_path)? {
+        file_utils::copy_file(&format!("{}{}", snapshot_path, save_file), &format!("{}{}", save_path, save_file))?;
+    }
+    debug!("Restored snapshot {} of Seed {}", snapshot_name, seed);
+    Ok(())
+}
+
+fn prune_snapshots(seed: &str) -> Result<(), String> {
+    let snapshots = list_snapshots(seed)?;
+    for snapshot in snapshots.iter().skip(SNAPSHOT_LIMIT) {
+        file_utils::delete_dir(&format!("{}{}", snapshots_path(seed), snapshot.name))?;
+    }
+    Ok(())
+}
+
+fn snapshot_names(seed: &str) -> Result<Vec<String>, String> {
+    let snapshots_path = snapshots_path(seed);
+    if !file_utils::path_exists(&snapshots_path, false)? {
+        return Ok(Vec::new());
+    }
+    Ok(file_utils::read_dir(&snapshots_path)?.filter_map(|snapshot| snapshot.ok())
+        .filter(|snapshot| snapshot.path().is_dir())
+        .map(|snapshot| snapshot.file_name().to_string_lossy().to_string())
+        .collect())
+}
+
+fn save_files(save_path: &str) -> Result<Vec<String>, String> {
+    if !file_utils::path_exists(save_path, false)? {
+        return Ok(Vec::new());
+    }
+    let mut save_files = file_utils::read_dir(save_path)?.filter_map(|save_file| save_file.ok())
+        .filter(|save_file| save_file.path().is_file())
+        .map(|save_file| save_file.file_name().to_string_lossy().to_string())
+        .filter(|save_file_name| save_file_name != SNAPSHOT_METADATA_FILE_NAME)
+        .collect::<Vec<String>>();
+    save_files.sort();
+    Ok(save_files)
+}
+
+// UTC, worked out by hand to avoid a date dependency for one label
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds_of_day = timestamp % 86400;
+
+    let shifted_days = days + 719468;
+    let era = shifted_days.div_euclid(146097);
+    let day_of_era = shifted_days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, seconds_of_day / 3600, seconds_of_day / 60 % 60)
+}
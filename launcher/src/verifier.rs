@@ -1,21 +1,23 @@
-use dirs_next;
+use game_versions::{self, GameVersion};
 use sha2::{Sha256, Digest};
 use sysinfo::System;
 
 use crate::ap_data::LaMulanaConfig;
 use crate::consts::*;
 use crate::file_utils;
+use crate::versions;
 
 pub fn verify_install() -> Result<LaMulanaConfig, String> {
-    let lm_config = verify_exe()?;
-    if lm_config.version == "1.6.6.2" {
+    let game_version = verify_exe()?;
+    if game_version.steam {
         verify_steam()?;
     }
+    let lm_config = versions::to_config(&game_version)?;
     verify_game_files(lm_config.clone())?;
     return Ok(lm_config.clone());
 }
 
-fn verify_exe() -> Result<LaMulanaConfig, String> {
+fn verify_exe() -> Result<GameVersion, String> {
     // Confirm that the LM executable actually exists (mostly protection against the launcher being run from the wrong location)
     let _ = file_utils::path_exists(&LAMULANA_EXECUTABLE_NAME_WITH_EXTENSION, true)?;
 
@@ -108,40 +110,11 @@ fn verify_lm_file(file_path: String, digest: String) -> Result<(), String> {
     }
 }
 
-fn fetch_config(digest: String) -> Result<LaMulanaConfig, String> {
-    if digest == "390E26B6A0C1F14BCAC521D2F8E410C4DEAD0B3E2693B2192BD6CA7832CB5B17" {
-        Ok(LaMulanaConfig {
-            version: "1.0.0.1".to_string(),
-            save_path: "save/".to_string(),
-            rcd_digest: "87437780618A3ABDE22BC7200B793FB900169E4F018D1F37D323AC6B5B2F120E".to_string(),
-            dat_digest: "E9F34854D82EBA1E72DD80C573DB1202AA15524FAAC3FC82C8D9F9943BD9F31C".to_string(),
-            effects_digest: "7CB3D2755ECE2E90BC88A81BCEA6C05350E4695182C3798F86F967A5D4BAC466".to_string()
-        })
-    } else {
-        let documents_dir = match dirs_next::document_dir() {
-            Some(doc_path) => doc_path.into_os_string().into_string().unwrap_or(String::new()),
-            None => String::new()
-        };
-        let save_path = format!("{}/nigoro/la-mulana/save/", documents_dir);
-
-        if digest == "E4B5EBE57017C5838DAB44D51C6330902B4FB333AD4714C0E8C8BD37FD354BC8" {
-            Ok(LaMulanaConfig {
-                version: "1.6.6.1".to_string(),
-                save_path: save_path,
-                rcd_digest: "583DCE2B2BB41E7A1927C6052F7A6AEFEE3F021A792E1AC587E2103C8B5D4CAC".to_string(),
-                dat_digest: "89A2AA21E2CB2DAD6DB5F2EEA474903927980384DE4BC868A9494B1DA3DFED2B".to_string(),
-                effects_digest: "7CB3D2755ECE2E90BC88A81BCEA6C05350E4695182C3798F86F967A5D4BAC466".to_string()
-            })
-        } else if digest == "94228016FFFF8A0BA6325140F0CFF6896E2BD0579BB2099D234508DEDE65923F" {
-            Ok(LaMulanaConfig {
-                version: "1.6.6.2".to_string(),
-                save_path: save_path,
-                rcd_digest: "583DCE2B2BB41E7A1927C6052F7A6AEFEE3F021A792E1AC587E2103C8B5D4CAC".to_string(),
-                dat_digest: "89A2AA21E2CB2DAD6DB5F2EEA474903927980384DE4BC868A9494B1DA3DFED2B".to_string(),
-                effects_digest: "7CB3D2755ECE2E90BC88A81BCEA6C05350E4695182C3798F86F967A5D4BAC466".to_string()
-            })
-        } else {
-            Err(format!("Your version of {} appears to be an unsupported version or modded. Please ensure it's an unaltered copy and that it's either version 1.0.0.1, 1.6.6.1, or 1.6.6.2", *LAMULANA_EXECUTABLE_NAME_WITH_EXTENSION))
-        }
-    }
+fn fetch_config(digest: String) -> Result<GameVersion, String> {
+    let versions = game_versions::load_versions()?;
+    let supported_versions = versions.iter().map(|game_version| game_version.version.clone()).collect::<Vec<String>>();
+
+    versions.into_iter().find(|game_version| game_version.exe_digest == digest).ok_or_else(|| {
+        format!("Your version of {} appears to be an unsupported version or modded. Please ensure it's an unaltered copy and that it's one of the supported versions: {}", *LAMULANA_EXECUTABLE_NAME_WITH_EXTENSION, supported_versions.join(", "))
+    })
 }
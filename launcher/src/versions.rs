@@ -0,0 +1,26 @@
+use dirs_next;
+use game_versions::GameVersion;
+
+use crate::ap_data::LaMulanaConfig;
+
+pub fn to_config(game_version: &GameVersion) -> Result<LaMulanaConfig, String> {
+    let save_path = match game_version.save_path.as_str() {
+        "install" => "save/".to_string(),
+        "documents" => {
+            let documents_dir = match dirs_next::document_dir() {
+                Some(doc_path) => doc_path.into_os_string().into_string().unwrap_or(String::new()),
+                None => String::new()
+            };
+            format!("{}/nigoro/la-mulana/save/", documents_dir)
+        },
+        save_path => return Err(format!("Unknown save path {} for version {}", save_path, game_version.version))
+    };
+
+    Ok(LaMulanaConfig {
+        version: game_version.version.clone(),
+        save_path,
+        rcd_digest: game_version.rcd_digest.clone(),
+        dat_digest: game_version.dat_digest.clone(),
+        effects_digest: game_version.effects_digest.clone()
+    })
+}
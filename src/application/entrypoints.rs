@@ -1,19 +1,29 @@
 use archipelago_api::api::*;
 use archipelago_api::client::APClient;
 use log::{debug, warn};
+use std::cmp::max;
 use std::collections::{HashMap, VecDeque};
 use std::sync::{LazyLock, Mutex, MutexGuard};
 
-use crate::get_application;
-use crate::lm_structs::items::ARCHIPELAGO_ITEM_LOOKUP;
-use crate::lm_structs::script_header::{ScriptHeader, ScriptSubHeader};
+use crate::application::Application;
+use crate::application::game_memory::{GameMemory, grant_filler, grant_item, next_progressive_tier};
+use crate::application::goal::{self, Goal};
+use crate::application::guard::{self, guard, Feature};
+use crate::application::hints::{self, HintRequest};
+use crate::application::message_feed;
+use crate::application::shops::{self, ShopScouting};
+use crate::application::session::{self, ConnectionState, set_connection_state};
+use crate::application::text_arena::TEXT_ARENA;
+use crate::{ArchipelagoItem, FillerItem, ProgressiveItem, get_application};
+use crate::lm_structs::items::{ARCHIPELAGO_ITEM_LOOKUP, Item};
 use crate::lm_structs::taskdata::{EventWithBool, TaskData};
 use crate::screenplay;
 
 #[derive(Debug)]
 pub struct GivenItem {
     pub player_id: i64,
-    pub item_id: u32
+    pub item_id: u32,
+    pub item_name: String
 }
 
 #[derive(Clone)]
@@ -25,100 +35,282 @@ pub struct PlayerItem {
 #[derive(Clone)]
 pub struct NetworkItemForPlayer {
     pub network_item: NetworkItem,
-    pub rooms: Vec<String>
-}
-
-pub struct PlayerItemPopup {
-    pub popup_id_address: usize,
-    pub popup_id: u32,
-    pub encoded: Vec<u16>
+    pub rooms: Vec<String>,
+    // Position in the server's received items, counted as processed by the save only once it is delivered
+    pub index: u16
 }
 
 static PLAYER_ITEMS: LazyLock<Mutex<HashMap<i32, PlayerItem>>> = LazyLock::new(|| { Mutex::new(HashMap::new()) });
-static PLAYER_ITEM_POPUP: Mutex<Option<PlayerItemPopup>> = Mutex::new(None);
+static ITEM_SUMMARIES: LazyLock<Mutex<HashMap<i32, Vec<GivenItem>>>> = LazyLock::new(|| { Mutex::new(HashMap::new()) });
 static SYNC_REQUIRED: Mutex<bool> = Mutex::new(false);
-static GAME_COMPLETE: Mutex<bool> = Mutex::new(false);
 static ITEMS_TO_GIVE: Mutex<VecDeque<NetworkItemForPlayer>> = Mutex::new(VecDeque::new());
-static DEFAULT_POPUP_SCRIPT: LazyLock<Vec<u16>> = LazyLock::new(|| { vec![0x100,0x000a] });
+// How many received items have been queued or delivered for the loaded save, None until the server first sends them
+static ITEMS_QUEUED_THROUGH: Mutex<Option<u16>> = Mutex::new(None);
+static MESSAGE_POPUPS: Mutex<VecDeque<Vec<String>>> = Mutex::new(VecDeque::new());
+static DISPLAYED_MESSAGE: Mutex<Option<Vec<String>>> = Mutex::new(None);
 static RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| { tokio::runtime::Runtime::new().unwrap() });
 
+const MAX_SUMMARY_LINES: usize = 8;
+const RECEIVED_ITEMS_INDEX_FLAGS: (usize, usize) = (0x867, 0x868);
+// Set once the save counts processed items in the index flags, older launchers started them at 1 and advanced them per packet
+const RECEIVED_ITEMS_COUNTED_FLAG: usize = 0x86a;
+// Items for other players are placed as Holy Grail (Full). Message popups reuse its popup, their text replaces the item's anyway
+const OTHER_PLAYER_ITEM_ID: u32 = 83;
+
 pub type FnGameLoop = extern "C" fn();
 pub type FnPopupDialogDrawIntercept = extern "C" fn(&TaskData);
 pub type FnItemSymbolInitIntercept = extern "C" fn(&mut TaskData);
 
 pub fn game_loop() {
     let application = get_application();
-    let game_init: &mut u32 = application.read_address("game_init");
-    let global_flags: &mut [u8;4096] = application.read_address("global_flags");
-    let system_flags: &[u32;16] = application.read_address("system_flags");
+    guard::advance_frame();
+    guard(Feature::GameLoop, || format!("{} items queued", ITEMS_TO_GIVE.try_lock().map(|items| items.len()).unwrap_or(0)), || process_game_loop(application));
+    application.original_game_loop()
+}
 
-    if (system_flags[3] & 0x20000) == 0x20000 {
+fn process_game_loop(application: &Application) {
+    let goal = Goal::from_config(application.get_app_config());
+    if goal::escape_started(application) {
+        // Only the status goes out while the escape and credits play, and only the goal's acknowledgement is waited for
+        goal::check_goal(application, &goal);
+        session::update_client_status(application);
+        if session::status_update_pending() || goal::acknowledgement_pending() {
+            std::thread::spawn(move || {
+                RUNTIME.block_on(send_client_status());
+            });
+        }
+        display_next_message(application);
+    } else if randomizer_save_loaded(application) {
+        session::update_client_status(application);
+        goal::check_goal(application, &goal);
+        if let Ok(mut text_arena) = TEXT_ARENA.try_lock() {
+            text_arena.release_finished(application);
+        }
+        hints::poll_hint_hotkey(&application.get_app_config().hint_hotkey);
+        if ShopScouting::from_config(&application.get_app_config().shop_scouting) != ShopScouting::Off {
+            shops::detect_opened_shop(application, &application.get_app_config().shop_scouts);
+        }
         std::thread::spawn(move || {
-            let _ = GAME_COMPLETE.try_lock().map(|mut game_complete| {
-                if *game_complete == false {
-                    *game_complete = true;
-                    RUNTIME.block_on(send_game_complete_notice());
+            RUNTIME.block_on(get_updates_from_server());
+        });
+
+        if let Ok(mut items_to_give) = ITEMS_TO_GIVE.try_lock() {
+            // Too many items to animate one at a time, grant them all at once and show a single summary
+            if items_to_give.iter().filter(|item_to_give| item_to_give.rooms.is_empty()).count() > application.get_app_config().item_summary_threshold {
+                give_item_summary(application, &mut items_to_give, &application.get_app_config().fillers(), &application.get_app_config().progressives());
+            } else if let Some((filler_item, player_id)) = deliver_next_filler(application, &mut items_to_give, &application.get_app_config().fillers()) {
+                let player_name = application.get_app_config().players_lookup().get(&player_id).cloned().unwrap_or("Server".to_string());
+                queue_message_popup(vec![format!("{} From {}", filler_item.item_name, player_name)]);
+            } else if let Some((lm_item, player_id)) = deliver_next_item(application, &mut items_to_give, &application.get_app_config().progressives()) {
+                if let Ok(ref mut player_items) = PLAYER_ITEMS.lock() {
+                    player_items.insert(lm_item.item_id as i32, PlayerItem {
+                        player_id,
+                        for_player: false
+                    });
                 }
+            } else {
+                display_next_message(application);
+            }
+            if let Ok(queued_through) = ITEMS_QUEUED_THROUGH.try_lock() {
+                update_received_item_count(application, &items_to_give, *queued_through);
+            }
+        }
+    } else {
+        // Back on the title screen, nothing but the status is exchanged until a save is loaded again.
+        // Items still queued belong to the save that was left, the next save loaded resyncs from its own count
+        if let (Ok(mut items_to_give), Ok(mut queued_through)) = (ITEMS_TO_GIVE.try_lock(), ITEMS_QUEUED_THROUGH.try_lock()) {
+            items_to_give.clear();
+            *queued_through = None;
+            *SYNC_REQUIRED.lock().unwrap() = true;
+        }
+        session::update_client_status(application);
+        if session::status_update_pending() || goal::acknowledgement_pending() {
+            std::thread::spawn(move || {
+                RUNTIME.block_on(send_client_status());
             });
+        }
+    }
+}
+
+pub fn queue_message_popup(lines: Vec<String>) {
+    if let Ok(mut message_popups) = MESSAGE_POPUPS.lock() {
+        message_popups.push_back(lines);
+    }
+}
+
+// Popups are matched to their text by item id, so a message waits until no other popup is pending
+fn display_next_message(memory: &dyn GameMemory) {
+    let Ok(mut displayed_message) = DISPLAYED_MESSAGE.try_lock() else { return };
+    let popups_pending = PLAYER_ITEMS.try_lock().map_or(true, |player_items| !player_items.is_empty())
+        || ITEM_SUMMARIES.try_lock().map_or(true, |item_summaries| !item_summaries.is_empty());
+    if displayed_message.is_some() || popups_pending {
+        return;
+    }
+
+    if let Some(lines) = MESSAGE_POPUPS.try_lock().ok().and_then(|mut message_popups| message_popups.pop_front()) {
+        *displayed_message = Some(lines);
+        memory.spawn_message_popup(OTHER_PLAYER_ITEM_ID);
+    }
+}
+
+pub fn randomizer_save_loaded(memory: &dyn GameMemory) -> bool {
+    memory.game_initialized() && memory.global_flag(0x863) > 0
+}
+
+// Fillers are granted straight into the inventory, with a message popup instead of an item get
+pub fn deliver_next_filler(memory: &dyn GameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, filler_items: &HashMap<i64, FillerItem>) -> Option<(FillerItem, i64)> {
+    let filler_item = filler_items.get(&items_to_give.front()?.network_item.item)?.clone();
+    let ap_item = items_to_give.pop_front()?;
+    grant_filler(memory, &filler_item);
+    Some((filler_item, ap_item.network_item.player))
+}
+
+// Takes the next queued item and spawns its item get, unless one was already tried in this room.
+// Items stay queued until their flag is set, and are only dropped once an unrepeatable item is already owned
+pub fn deliver_next_item(memory: &dyn GameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, progressive_items: &HashMap<i64, ProgressiveItem>) -> Option<(&'static Item, i64)> {
+    let mut ap_item = items_to_give.pop_front()?;
+    // A progressive copy becomes a fixed tier once its item get is spawned, so it is resolved against what is owned right now
+    if let Some(progressive_item) = progressive_items.get(&ap_item.network_item.item) {
+        ap_item.network_item.item = next_progressive_tier(memory, progressive_item, &in_flight_items(items_to_give))?;
+    }
+    let lm_item = ARCHIPELAGO_ITEM_LOOKUP.get(&ap_item.network_item.item).unwrap();
+
+    let give_item = lm_item.item_id > 104 || memory.global_flag(lm_item.flag) == 0;
+    if !give_item {
+        return None;
+    }
+
+    let room_index = memory.current_room().to_string();
+    if ap_item.rooms.contains(&room_index) {
+        items_to_give.push_back(ap_item);
+        return None;
+    }
+
+    let player_id = ap_item.network_item.player;
+    let mut rooms = ap_item.rooms.clone();
+    rooms.push(room_index);
+    items_to_give.push_back(NetworkItemForPlayer { network_item: ap_item.network_item, rooms, index: ap_item.index });
+
+    memory.spawn_item_get(lm_item);
+    Some((lm_item, player_id))
+}
+
+// Items whose item get was spawned but hasn't set their flag yet
+fn in_flight_items(items_to_give: &VecDeque<NetworkItemForPlayer>) -> Vec<i64> {
+    items_to_give.iter().filter(|item_to_give| !item_to_give.rooms.is_empty()).map(|item_to_give| item_to_give.network_item.item).collect()
+}
+
+// Items before the save's count or already queued this session are skipped, so each one is delivered once across full syncs, reconnects and reloads.
+// Returns whether items were missed, in which case a full sync is needed
+pub fn queue_received_items(memory: &dyn GameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, queued_through: &mut Option<u16>, received_items: ReceivedItems, local_player_id: i64, filler_items: &HashMap<i64, FillerItem>, progressive_items: &HashMap<i64, ProgressiveItem>) -> bool {
+    let ReceivedItems { index, items: network_items } = received_items;
+    if memory.global_flag(RECEIVED_ITEMS_COUNTED_FLAG) == 0 {
+        if index > 0 {
+            return true;
+        }
+        queue_legacy_received_items(memory, items_to_give, queued_through, network_items, local_player_id, filler_items);
+        return false;
+    }
+
+    let processed_items = max(received_item_count(memory), queued_through.unwrap_or(0));
+    if index > processed_items {
+        return true;
+    }
+
+    let received_count = index + network_items.len() as u16;
+    for (offset, network_item) in network_items.into_iter().enumerate().skip((processed_items - index) as usize) {
+        // Items from our own world were picked up in place, only progressive copies come back through the server to get their tier
+        if network_item.player == local_player_id && !progressive_items.contains_key(&network_item.item) {
+            continue;
+        }
+        items_to_give.push_back(NetworkItemForPlayer {
+            network_item,
+            rooms: Vec::new(),
+            index: index + offset as u16
         });
-    } else if *game_init != 0 && global_flags[0x863] > 0 {
-        display_item_if_available();
-        std::thread::spawn(move || {
-            RUNTIME.block_on(get_updates_from_server());
+    }
+    *queued_through = Some(max(processed_items, received_count));
+    false
+}
+
+// A save whose index flags can't be trusted is resynced from the first item, queueing only what the save shows no sign of.
+// Fillers leave no flag behind, so they are never regranted. The save keeps resyncing this way until everything queued is delivered
+fn queue_legacy_received_items(memory: &dyn GameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, queued_through: &mut Option<u16>, network_items: Vec<NetworkItem>, local_player_id: i64, filler_items: &HashMap<i64, FillerItem>) {
+    let received_items = network_items.len() as u16;
+    for (offset, network_item) in network_items.into_iter().enumerate() {
+        let owned = ARCHIPELAGO_ITEM_LOOKUP.get(&network_item.item).is_some_and(|lm_item| memory.global_flag(lm_item.flag) != 0);
+        let queued = items_to_give.iter().any(|item_to_give| item_to_give.index == offset as u16);
+        if network_item.player == local_player_id || filler_items.contains_key(&network_item.item) || owned || queued {
+            continue;
+        }
+        items_to_give.push_back(NetworkItemForPlayer {
+            network_item,
+            rooms: Vec::new(),
+            index: offset as u16
         });
+    }
+    *queued_through = Some(received_items);
+}
 
-        let item_lock = ITEMS_TO_GIVE.try_lock();
-        if item_lock.is_ok() {
-            let mut items_to_give = item_lock.unwrap();
-            let item_to_give = items_to_give.pop_front();
-            match item_to_give {
-                Some(ap_item) => {
-                    let ap_item_id = ap_item.network_item.item;
-                    let lm_item = ARCHIPELAGO_ITEM_LOOKUP.get(&(ap_item_id)).unwrap();
-
-                    let give_item = lm_item.item_id > 104 || global_flags[lm_item.flag] == 0;
-
-                    if give_item {
-                        let mut rooms = ap_item.rooms.clone();
-
-                        let field: &mut u8 = application.read_address("current_field");
-                        let screen: &mut u8 = application.read_address("current_screen");
-                        let scene: &mut u8 = application.read_address("current_scene");
-                        let room_index = format!("{},{},{}", field, scene, screen);
-
-                        if rooms.contains(&room_index) {
-                            items_to_give.push_back(ap_item);
-                        } else {
-                            let player_id = ap_item.network_item.player;
-                            rooms.push(room_index);
-                            items_to_give.push_back(NetworkItemForPlayer { network_item: ap_item.network_item, rooms });
-                            if let Ok(ref mut player_items) = PLAYER_ITEMS.lock() {
-                                player_items.insert(lm_item.item_id as i32, PlayerItem {
-                                    player_id,
-                                    for_player: false
-                                });
-                            }
-
-                            application.give_item(&lm_item);
-                        }
-                    }
-                },
-                None => ()
+// Everything before the first item still waiting is processed, or everything queued once nothing is left waiting.
+// Items are only counted once delivered, so anything lost to a quit or crash is queued again when the save is next loaded
+pub fn update_received_item_count(memory: &dyn GameMemory, items_to_give: &VecDeque<NetworkItemForPlayer>, queued_through: Option<u16>) {
+    let Some(queued_through) = queued_through else { return };
+    let counted = memory.global_flag(RECEIVED_ITEMS_COUNTED_FLAG) != 0;
+    match items_to_give.iter().map(|item_to_give| item_to_give.index).min() {
+        Some(first_waiting) if counted => {
+            if first_waiting > received_item_count(memory) {
+                set_received_item_count(memory, first_waiting);
+            }
+        },
+        Some(_) => {},
+        None => {
+            if !counted || queued_through > received_item_count(memory) {
+                set_received_item_count(memory, queued_through);
             }
+            memory.set_global_flag(RECEIVED_ITEMS_COUNTED_FLAG, 1);
         }
     }
-    application.original_game_loop()
+}
+
+pub fn found_locations(memory: &dyn GameMemory, items: &HashMap<u16, ArchipelagoItem>) -> Vec<i64> {
+    items.iter().filter(|(flag, _)|
+        memory.global_flag(**flag as usize) == 2
+    ).map(|(_, item)|
+        item.location_id
+    ).collect()
+}
+
+pub fn received_item_count(memory: &dyn GameMemory) -> u16 {
+    ((memory.global_flag(RECEIVED_ITEMS_INDEX_FLAGS.0) as u16) << 8) | memory.global_flag(RECEIVED_ITEMS_INDEX_FLAGS.1) as u16
+}
+
+fn set_received_item_count(memory: &dyn GameMemory, received_item_count: u16) {
+    memory.set_global_flag(RECEIVED_ITEMS_INDEX_FLAGS.0, (received_item_count >> 8) as u8);
+    memory.set_global_flag(RECEIVED_ITEMS_INDEX_FLAGS.1, received_item_count as u8);
 }
 
 pub fn popup_dialog_draw_intercept(popup_dialog: &'static TaskData) {
     let application = get_application();
+    guard(Feature::PopupText, || format!("popup task {}, item {}", popup_dialog.id.uid, popup_dialog.sbuff[0]), || overwrite_popup_text(application, popup_dialog));
+    application.popup_dialog_draw(popup_dialog)
+}
+
+fn overwrite_popup_text(application: &Application, popup_dialog: &'static TaskData) {
     let mut player_items = PLAYER_ITEMS.lock().unwrap();
+    let mut item_summaries = ITEM_SUMMARIES.lock().unwrap();
 
-    if let Some(player_item) = player_items.get(&popup_dialog.sbuff[0]) {
-        let script_header: &*const ScriptHeader = application.read_address("script_header_pointer");
-        let line_header = unsafe { (*script_header.add(3)).data as *mut ScriptSubHeader};
-        let line = unsafe { &mut *line_header.add(2) };
+    let displayed_message = if popup_dialog.sbuff[0] == OTHER_PLAYER_ITEM_ID as i32 {
+        DISPLAYED_MESSAGE.lock().unwrap().take()
+    } else {
+        None
+    };
 
+    let encoded_popup_text = if let Some(given_items) = item_summaries.remove(&popup_dialog.sbuff[0]) {
+        Some(screenplay::encode_lines(item_summary_lines(given_items)))
+    } else if let Some(lines) = displayed_message {
+        Some(screenplay::encode_lines(lines))
+    } else if let Some(player_item) = player_items.remove(&popup_dialog.sbuff[0]) {
         let popup_text = if player_item.for_player {
             format!("  For Another Player!")
         } else {
@@ -128,28 +320,16 @@ pub fn popup_dialog_draw_intercept(popup_dialog: &'static TaskData) {
             let player_name = players.get(player_id).unwrap_or(&server_name);
             format!("  From {player_name}!")
         };
-        let space_count = popup_text.chars().filter(|c| *c == ' ').count();
-        let mut encoded_popup_text = screenplay::encode(popup_text);
-        encoded_popup_text.push(0x000a);
-        player_items.remove(&popup_dialog.sbuff[0]);
-
-        let popup = PlayerItemPopup {
-            popup_id_address: &popup_dialog.id.uid as *const u32 as usize,
-            popup_id: popup_dialog.id.uid,
-            encoded: encoded_popup_text
-        };
-
-        let mut popup_option = PLAYER_ITEM_POPUP.lock().unwrap();
-        *popup_option = Some(popup);
-        let popup = popup_option.as_ref().unwrap();
+        Some(screenplay::encode_lines(vec![popup_text]))
+    } else {
+        None
+    };
 
-        *line = ScriptSubHeader {
-            pointer: popup.encoded.as_ptr() as usize,
-            data_num: popup.encoded.len() as i32,
-            font_num: (popup.encoded.len() - space_count - 1) as i32
-        };
+    if let Some(encoded_popup_text) = encoded_popup_text {
+        if let Ok(mut text_arena) = TEXT_ARENA.lock() {
+            text_arena.overwrite(application, popup_dialog, 3, 2, encoded_popup_text);
+        }
     }
-    application.popup_dialog_draw(popup_dialog)
 }
 
 pub fn item_symbol_init_intercept(item: &'static mut TaskData) {
@@ -160,33 +340,41 @@ pub fn item_symbol_init_intercept(item: &'static mut TaskData) {
         application.original_item_symbol_init(&mut *raw_item);
     }
 
-    item.rfunc = item_symbol_back_intercept as EventWithBool;
+    let item_uid = item.id.uid;
+    guard(Feature::ItemSymbolInit, || format!("item task {}", item_uid), || {
+        item.rfunc = item_symbol_back_intercept as EventWithBool;
+    });
 }
 
 pub fn item_symbol_back_intercept(item: &mut TaskData) -> u32 {
     let application = get_application();
     let acquired = item.hit_data > 0;
     let item_id = item.buff[1];
-    let for_other_player = item_id == 83;
+    let for_other_player = item_id == OTHER_PLAYER_ITEM_ID as i32;
+    let item_uid = item.id.uid;
 
-    if for_other_player {
-        item.sbuff[2] = 0;
-    }
+    guard(Feature::ItemSymbolBack, || format!("item task {}, item {}", item_uid, item_id), || {
+        if for_other_player {
+            item.sbuff[2] = 0;
+        }
+    });
 
     let item_symbol_back: &*const () = application.read_address("item_symbol_back");
     let item_symbol_back_func: extern "C" fn(&TaskData) -> u32 = unsafe { std::mem::transmute(item_symbol_back) };
     let result = (item_symbol_back_func)(item);
 
-    if acquired && for_other_player {
-        if let Ok(ref mut player_items) = PLAYER_ITEMS.lock() {
-            player_items.insert(item_id, PlayerItem {
-                for_player: true,
-                player_id: 0
-            });
-        }
+    guard(Feature::ItemSymbolBack, || format!("item task {}, item {}", item_uid, item_id), || {
+        if acquired && for_other_player {
+            if let Ok(ref mut player_items) = PLAYER_ITEMS.lock() {
+                player_items.insert(item_id, PlayerItem {
+                    for_player: true,
+                    player_id: 0
+                });
+            }
 
-        application.create_dialog_popup(item_id as u32);
-    }
+            application.create_dialog_popup(item_id as u32);
+        }
+    });
 
     result
 }
@@ -196,28 +384,73 @@ pub fn default_final_intercept(give_item_task: &mut TaskData) {
 
     let item_flag = give_item_task.sbuff[31] as usize;
 
-    let global_flags: &mut [u8;4096] = application.read_address("global_flags");
-    global_flags[item_flag] = 2;
+    guard(Feature::ItemFlag, || format!("give item task {}, flag {}", give_item_task.id.uid, item_flag), || {
+        let global_flags: &mut [u8;4096] = application.read_address("global_flags");
+        global_flags[item_flag] = 2;
+    });
 
     let default_final: &*const () = application.read_address("default_final");
     let default_final_func: extern "C" fn(&TaskData) = unsafe { std::mem::transmute(default_final) };
     (default_final_func)(give_item_task);
 }
 
-fn display_item_if_available() {
-    let application = get_application();
-    if let Some(popup_option) = PLAYER_ITEM_POPUP.try_lock().ok().as_mut() {
-        if let Some(popup) = popup_option.as_ref() {
-            if popup.popup_id != *application.read_raw_address::<u32>(popup.popup_id_address) {
-                let script_header: &*const ScriptHeader = application.read_address("script_header_pointer");
-                let line_header = unsafe { (*script_header.add(3)).data as *mut ScriptSubHeader};
-                let line = unsafe { &mut *line_header.add(2) };
-                line.pointer = DEFAULT_POPUP_SCRIPT.as_ptr() as usize;
-                line.data_num = 2;
-                line.font_num = 1;
-            }
+// Items with an item get already spawned stay queued, since their task grants them once it finishes
+fn give_item_summary(memory: &dyn GameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, filler_items: &HashMap<i64, FillerItem>, progressive_items: &HashMap<i64, ProgressiveItem>) {
+    let (in_flight, undispatched): (VecDeque<NetworkItemForPlayer>, Vec<NetworkItemForPlayer>) = items_to_give.drain(..).partition(|ap_item| !ap_item.rooms.is_empty());
+    *items_to_give = in_flight;
+    let in_flight_items = in_flight_items(items_to_give);
+
+    let given_items = undispatched.into_iter().filter_map(|ap_item| {
+        if let Some(filler_item) = filler_items.get(&ap_item.network_item.item) {
+            grant_filler(memory, filler_item);
+            return Some(GivenItem {
+                player_id: ap_item.network_item.player,
+                item_id: filler_item.item_id,
+                item_name: filler_item.item_name.clone()
+            });
+        }
+
+        // Each tier is granted before the next copy is resolved, so copies in the same summary climb through the tiers
+        let ap_item_id = match progressive_items.get(&ap_item.network_item.item) {
+            Some(progressive_item) => next_progressive_tier(memory, progressive_item, &in_flight_items)?,
+            None => ap_item.network_item.item
+        };
+        let lm_item = ARCHIPELAGO_ITEM_LOOKUP.get(&ap_item_id)?;
+        if lm_item.item_id > 104 || memory.global_flag(lm_item.flag) == 0 {
+            grant_item(memory, lm_item);
+            Some(GivenItem {
+                player_id: ap_item.network_item.player,
+                item_id: lm_item.item_id as u32,
+                item_name: lm_item.item_name.to_string()
+            })
+        } else {
+            None
+        }
+    }).collect::<Vec<GivenItem>>();
+
+    if let Some(first_item) = given_items.first() {
+        let item_id = first_item.item_id;
+        if let Ok(ref mut item_summaries) = ITEM_SUMMARIES.lock() {
+            item_summaries.insert(item_id as i32, given_items);
         }
+        memory.spawn_item_popup(item_id);
+    }
+}
+
+fn item_summary_lines(given_items: Vec<GivenItem>) -> Vec<String> {
+    let application = get_application();
+    let players = application.get_app_config().players_lookup();
+    let server_name = "Server".to_string();
+
+    let mut lines = Vec::new();
+    for given_item in given_items.iter().take(MAX_SUMMARY_LINES) {
+        let player_name = players.get(&given_item.player_id).unwrap_or(&server_name);
+        lines.push(format!("{} From {}", given_item.item_name, player_name));
     }
+    if given_items.len() > MAX_SUMMARY_LINES {
+        lines.push(format!("  and {} More!", given_items.len() - MAX_SUMMARY_LINES));
+    }
+    lines
 }
 
 async fn get_updates_from_server() {
@@ -251,12 +484,7 @@ async fn get_updates_from_server() {
         }
     }
 
-    let global_flags: &mut [u8;4096] = application.read_address("global_flags");
-    let found_items: Vec<i64> = application.get_app_config().items().iter().filter(|(k,_)|
-        global_flags[**k as usize] == 2
-    ).map(|(_,v)|
-        v.location_id
-    ).collect();
+    let found_items = found_locations(application, &application.get_app_config().items());
 
     // Send List of Found Items to Server, Attempt Reconnect if Network Error
     match randomizer.location_checks(found_items).await {
@@ -268,31 +496,96 @@ async fn get_updates_from_server() {
         }
     }
 
+    // Ask the Server for a Hint if the Hint Hotkey was Pressed
+    match hints::take_hint_request(application, &application.get_app_config().hint_items, &application.get_app_config().progressives()) {
+        Some(HintRequest::Send(item_name)) => {
+            if let Err(e) = randomizer.say(format!("!hint {}", item_name)).await {
+                warn!("Failed to request hint with error {}", e);
+            }
+        },
+        Some(HintRequest::NotEnoughPoints(hint_points, hint_cost)) => {
+            queue_message_popup(vec![format!("Not enough hint points ({}/{})", hint_points, hint_cost)]);
+        },
+        Some(HintRequest::NothingToHint) => {
+            queue_message_popup(vec!["Nothing left to hint".to_string()]);
+        },
+        None => {}
+    }
+
+    // Report Client Status Changes, Attempt Reconnect and Report Again if Network Error
+    if let Some(client_status) = session::take_status_update() {
+        if let Err(e) = randomizer.status_update(client_status).await {
+            warn!("Status Update Failure with error {:?}, attempting reconnect", e);
+            session::status_update_failed();
+            connect_to_server(randomizer_lock).await;
+            return
+        }
+    }
+
+    // Scout Any Shop Opened Since the Last Update
+    let shop_scouting = ShopScouting::from_config(&application.get_app_config().shop_scouting);
+    let scouted_locations = shops::take_pending_scouts();
+    if !scouted_locations.is_empty() {
+        if let Err(e) = randomizer.location_scouts(scouted_locations.clone(), shop_scouting.create_as_hint()).await {
+            warn!("Failed to scout shop with error {}", e);
+            shops::requeue_scouts(scouted_locations);
+        }
+    }
+
+    read_from_server(application, randomizer_lock, true).await;
+}
+
+// Items are only queued while a save is being played, a skipped packet leaves a gap the next one resyncs
+async fn read_from_server(application: &Application, mut randomizer_lock: MutexGuard<'_, Result<APClient, APError>>, receive_items: bool) {
+    let Ok(randomizer) = randomizer_lock.as_mut() else { return };
+    let shop_scouting = ShopScouting::from_config(&application.get_app_config().shop_scouting);
+
     // Read Next Message From Server
     match randomizer.read().await {
         Ok(response) => {
             match response {
-                ServerPayload::ReceivedItems(received_items) => {
-                    if received_items.index > 0 {
-                        let mut received_item_index = ((global_flags[0x867] as u16) << 8) | global_flags[0x868] as u16;
-                        if received_item_index != received_items.index {
-                            *SYNC_REQUIRED.lock().unwrap() = true;
-                        }
-                        received_item_index += 1;
-                        global_flags[0x867] = (received_item_index >> 8) as u8;
-                        global_flags[0x868] = received_item_index as u8;
+                ServerPayload::RoomInfo(room_info) => {
+                    hints::set_hint_cost(room_info.hint_cost);
+                },
+                ServerPayload::RoomUpdate(room_update) => {
+                    if let Some(hint_cost) = room_update.hint_cost {
+                        hints::set_hint_cost(hint_cost);
                     }
-
-                    let items_from_ap = received_items.items;
+                    if let Some(hint_points) = room_update.hint_points {
+                        hints::set_hint_points(hint_points);
+                    }
+                },
+                ServerPayload::Connected(connected) => {
+                    set_connection_state(ConnectionState::Connected);
+                    hints::set_hint_points(connected.hint_points);
+                    hints::set_location_count((connected.missing_locations.len() + connected.checked_locations.len()) as i64);
+                    let games = message_feed::on_connected(&connected);
+                    if let Err(e) = randomizer.get_data_package(games).await {
+                        warn!("Failed to request data package with error {}", e);
+                    }
+                },
+                ServerPayload::LocationInfo(location_info) => {
+                    // Hinted scouts are already announced through PrintJSON
+                    if shop_scouting == ShopScouting::Scout {
+                        message_feed::on_location_info(&location_info, &application.get_app_config().coin_chests());
+                    }
+                },
+                ServerPayload::DataPackage(data_package) => {
+                    message_feed::on_data_package(data_package);
+                },
+                ServerPayload::PrintJSON(print_json) => {
+                    goal::on_print_json(&print_json, application.get_app_config().local_player_id);
+                    message_feed::on_print_json(&print_json, &application.get_app_config().message_feed_types, &application.get_app_config().coin_chests());
+                },
+                ServerPayload::ConnectionRefused(connection_refused) => {
+                    set_connection_state(ConnectionState::Refused(connection_refused.errors));
+                },
+                ServerPayload::ReceivedItems(received_items) if receive_items => {
                     let mut items_to_give = ITEMS_TO_GIVE.lock().unwrap();
-                    for network_item in items_from_ap {
-                        if !items_to_give.iter().any(|item_to_give| item_to_give.network_item.item == network_item.item) {
-                            let item_for_player = NetworkItemForPlayer {
-                                network_item,
-                                rooms: Vec::new()
-                            };
-                            items_to_give.push_back(item_for_player);
-                        }
+                    let mut queued_through = ITEMS_QUEUED_THROUGH.lock().unwrap();
+                    let app_config = application.get_app_config();
+                    if queue_received_items(application, &mut items_to_give, &mut queued_through, received_items, app_config.local_player_id, &app_config.fillers(), &app_config.progressives()) {
+                        *SYNC_REQUIRED.lock().unwrap() = true;
                     }
                 },
                 _ => {}
@@ -304,7 +597,6 @@ async fn get_updates_from_server() {
                 APError::NoConnection => {
                     debug!("Connection to Server Lost, Attempting Reconnect");
                     connect_to_server(randomizer_lock).await;
-                    return
                 },
                 _ => {
                     debug!("Unexpected Binary Data from Server");
@@ -317,6 +609,7 @@ async fn get_updates_from_server() {
 pub async fn connect_to_server(mut randomizer: MutexGuard<'_, Result<APClient, APError>>) {
     let application = get_application();
     let app_config = application.get_app_config();
+    set_connection_state(ConnectionState::Connecting);
     *randomizer = APClient::new(&app_config.server_url).await;
     match randomizer.as_mut() {
         Ok(ap_client) => {
@@ -324,33 +617,274 @@ pub async fn connect_to_server(mut randomizer: MutexGuard<'_, Result<APClient, A
             let players = app_config.players_lookup();
             let player_name = players.get(&player_id).unwrap();
             let password = &app_config.password;
-            match ap_client.connect(password, "La-Mulana", &player_name, Some(player_id), ItemHandling::OtherWorldsOnly, vec![], false).await {
+            match ap_client.connect(password, "La-Mulana", &player_name, Some(player_id), ItemHandling::OtherWorldsAndSelf, vec![], false).await {
                 Ok(_) => {},
                 Err(e) => {
                     debug!("Connect Failure with error {:?}", e);
+                    set_connection_state(ConnectionState::Disconnected);
                 }
             }
         },
         Err(e) => {
             debug!("AP Client Not Connected with Error {}", e);
+            set_connection_state(ConnectionState::Disconnected);
         }
     };
 }
 
-pub async fn send_game_complete_notice() {
+// Used while no save is being played. Reconnecting and reading are only worth it while the goal still needs the server's acknowledgement
+async fn send_client_status() {
     let application = get_application();
-    let mut randomizer = application.get_randomizer().lock().unwrap();
-    match randomizer.as_mut() {
-        Ok(ap_client) => {
-            match ap_client.status_update(ClientStatus::ClientGoal).await {
-                Ok(_) => {},
-                Err(e) => {
-                    debug!("Game Completion Notice Failure with error {:?}", e);
-                }
-            }
-        },
-        Err(e) => {
-            debug!("AP Client Not Connected with Error {}", e);
+    let Ok(mut randomizer_lock) = application.get_randomizer().try_lock() else { return };
+    let Ok(randomizer) = randomizer_lock.as_mut() else {
+        if goal::acknowledgement_pending() {
+            connect_to_server(randomizer_lock).await;
         }
+        return
     };
+
+    if let Some(client_status) = session::take_status_update() {
+        if let Err(e) = randomizer.status_update(client_status).await {
+            warn!("Status Update Failure with error {:?}, attempting reconnect", e);
+            session::status_update_failed();
+            connect_to_server(randomizer_lock).await;
+            return
+        }
+    }
+
+    if goal::acknowledgement_pending() {
+        read_from_server(application, randomizer_lock, false).await;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::game_memory::{FakeGameMemory, Room};
+
+    const CHAIN_WHIP: i64 = 2359001;
+    const FLAIL_WHIP: i64 = 2359002;
+    const HAND_SCANNER: i64 = 2359030;
+    const SHELL_HORN: i64 = 2359051;
+    // Not in the item table, progressive ids only come from the generated config
+    const PROGRESSIVE_WHIP: i64 = 2359900;
+    const LOCAL_PLAYER: i64 = 1;
+    const OTHER_PLAYER: i64 = 2;
+
+    fn network_item(item: i64, player: i64) -> NetworkItem {
+        NetworkItem { item, location: 0, player, flags: 0 }
+    }
+
+    fn queued(items: &[i64]) -> VecDeque<NetworkItemForPlayer> {
+        items.iter().enumerate().map(|(index, item)| NetworkItemForPlayer { network_item: network_item(*item, OTHER_PLAYER), rooms: Vec::new(), index: index as u16 }).collect()
+    }
+
+    fn queued_items(items_to_give: &VecDeque<NetworkItemForPlayer>) -> Vec<i64> {
+        items_to_give.iter().map(|item_to_give| item_to_give.network_item.item).collect()
+    }
+
+    fn progressive_whip() -> HashMap<i64, ProgressiveItem> {
+        HashMap::from([(PROGRESSIVE_WHIP, ProgressiveItem {
+            ap_id: PROGRESSIVE_WHIP,
+            item_name: "Progressive Whip".to_string(),
+            tiers: vec![CHAIN_WHIP, FLAIL_WHIP]
+        })])
+    }
+
+    #[test]
+    fn deliver_next_item_spawns_the_item_and_drops_it_once_owned() {
+        let memory = FakeGameMemory::new();
+        let mut items_to_give = queued(&[HAND_SCANNER]);
+
+        let (lm_item, player_id) = deliver_next_item(&memory, &mut items_to_give, &HashMap::new()).unwrap();
+        assert_eq!(lm_item.item_name, "Hand Scanner");
+        assert_eq!(player_id, OTHER_PLAYER);
+        assert_eq!(*memory.spawned_items.borrow(), vec![20]);
+        assert_eq!(items_to_give.len(), 1);
+        assert_eq!(items_to_give[0].rooms, vec![Room::default().to_string()]);
+
+        assert!(deliver_next_item(&memory, &mut items_to_give, &HashMap::new()).is_none());
+        assert!(items_to_give.is_empty());
+        assert_eq!(memory.spawned_items.borrow().len(), 1);
+    }
+
+    #[test]
+    fn deliver_next_item_retries_in_a_new_room_until_the_flag_is_set() {
+        let memory = FakeGameMemory::new();
+        let mut items_to_give = queued(&[HAND_SCANNER]);
+
+        deliver_next_item(&memory, &mut items_to_give, &HashMap::new()).unwrap();
+        // The item get was interrupted before the game granted it
+        memory.set_global_flag(0x96, 0);
+
+        assert!(deliver_next_item(&memory, &mut items_to_give, &HashMap::new()).is_none());
+        assert_eq!(items_to_give.len(), 1);
+
+        memory.room.set(Room { field: 1, scene: 0, screen: 0 });
+        assert!(deliver_next_item(&memory, &mut items_to_give, &HashMap::new()).is_some());
+        assert_eq!(*memory.spawned_items.borrow(), vec![20, 20]);
+        assert_eq!(items_to_give[0].rooms.len(), 2);
+    }
+
+    #[test]
+    fn deliver_next_item_resolves_progressive_copies_against_owned_and_in_flight_tiers() {
+        let memory = FakeGameMemory::new();
+        let mut items_to_give = queued(&[PROGRESSIVE_WHIP, PROGRESSIVE_WHIP]);
+
+        let (lm_item, _) = deliver_next_item(&memory, &mut items_to_give, &progressive_whip()).unwrap();
+        assert_eq!(lm_item.item_name, "Chain Whip");
+        // Still waiting on its item get, the next copy has to count it anyway
+        memory.set_global_flag(0x7d, 0);
+
+        let (lm_item, _) = deliver_next_item(&memory, &mut items_to_give, &progressive_whip()).unwrap();
+        assert_eq!(lm_item.item_name, "Flail Whip");
+        assert_eq!(queued_items(&items_to_give), vec![CHAIN_WHIP, FLAIL_WHIP]);
+    }
+
+    #[test]
+    fn deliver_next_item_drops_progressive_copies_once_every_tier_is_owned() {
+        let memory = FakeGameMemory::new();
+        memory.set_global_flag(0x7d, 2);
+        memory.set_global_flag(0x7e, 2);
+        let mut items_to_give = queued(&[PROGRESSIVE_WHIP]);
+
+        assert!(deliver_next_item(&memory, &mut items_to_give, &progressive_whip()).is_none());
+        assert!(items_to_give.is_empty());
+        assert!(memory.spawned_items.borrow().is_empty());
+    }
+
+    #[test]
+    fn found_locations_only_reports_checked_flags() {
+        let memory = FakeGameMemory::new();
+        memory.set_global_flag(0x10, 2);
+        memory.set_global_flag(0x11, 1);
+        let items = HashMap::from([
+            (0x10, ArchipelagoItem { flag: 0x10, location_id: 100, player_id: OTHER_PLAYER, obtain_value: 2 }),
+            (0x11, ArchipelagoItem { flag: 0x11, location_id: 101, player_id: OTHER_PLAYER, obtain_value: 2 }),
+            (0x12, ArchipelagoItem { flag: 0x12, location_id: 102, player_id: OTHER_PLAYER, obtain_value: 2 })
+        ]);
+
+        assert_eq!(found_locations(&memory, &items), vec![100]);
+    }
+
+    fn counting_save() -> FakeGameMemory {
+        let memory = FakeGameMemory::new();
+        memory.set_global_flag(RECEIVED_ITEMS_COUNTED_FLAG, 1);
+        memory
+    }
+
+    fn queue(memory: &FakeGameMemory, items_to_give: &mut VecDeque<NetworkItemForPlayer>, queued_through: &mut Option<u16>, index: u16, network_items: Vec<NetworkItem>) -> bool {
+        let fillers = HashMap::from([(SHELL_HORN, FillerItem { ap_id: SHELL_HORN, item_name: "Shell Horn".to_string(), item_id: 38, inventory_slot: 38, quantity: 1, max_quantity: 1 })]);
+        queue_received_items(memory, items_to_give, queued_through, ReceivedItems { index, items: network_items }, LOCAL_PLAYER, &fillers, &progressive_whip())
+    }
+
+    #[test]
+    fn queue_received_items_skips_items_already_queued_or_processed() {
+        let memory = counting_save();
+        let mut items_to_give = VecDeque::new();
+        let mut queued_through = None;
+        let network_items = vec![network_item(HAND_SCANNER, OTHER_PLAYER), network_item(CHAIN_WHIP, OTHER_PLAYER)];
+
+        assert!(!queue(&memory, &mut items_to_give, &mut queued_through, 0, network_items.clone()));
+        assert_eq!(queued_through, Some(2));
+
+        // A full sync after reconnecting repeats everything, plus whatever was sent while offline
+        let mut full_sync = network_items;
+        full_sync.push(network_item(FLAIL_WHIP, OTHER_PLAYER));
+        assert!(!queue(&memory, &mut items_to_give, &mut queued_through, 0, full_sync.clone()));
+        assert_eq!(queued_items(&items_to_give), vec![HAND_SCANNER, CHAIN_WHIP, FLAIL_WHIP]);
+        assert_eq!(items_to_give.iter().map(|item_to_give| item_to_give.index).collect::<Vec<u16>>(), vec![0, 1, 2]);
+
+        // After a reload only the save's count is left to go by
+        set_received_item_count(&memory, 2);
+        let mut items_to_give = VecDeque::new();
+        assert!(!queue(&memory, &mut items_to_give, &mut None, 0, full_sync));
+        assert_eq!(queued_items(&items_to_give), vec![FLAIL_WHIP]);
+    }
+
+    #[test]
+    fn queue_received_items_requests_a_sync_when_items_were_missed() {
+        let memory = counting_save();
+        let mut items_to_give = VecDeque::new();
+        let mut queued_through = None;
+
+        assert!(queue(&memory, &mut items_to_give, &mut queued_through, 1, vec![network_item(HAND_SCANNER, OTHER_PLAYER)]));
+        assert!(items_to_give.is_empty());
+        assert_eq!(queued_through, None);
+    }
+
+    #[test]
+    fn queue_received_items_only_keeps_progressive_copies_from_our_own_world() {
+        let memory = counting_save();
+        let mut items_to_give = VecDeque::new();
+        let mut queued_through = None;
+        let network_items = vec![network_item(HAND_SCANNER, LOCAL_PLAYER), network_item(PROGRESSIVE_WHIP, LOCAL_PLAYER)];
+
+        assert!(!queue(&memory, &mut items_to_give, &mut queued_through, 0, network_items));
+        assert_eq!(queued_items(&items_to_give), vec![PROGRESSIVE_WHIP]);
+        assert_eq!(items_to_give[0].index, 1);
+        assert_eq!(queued_through, Some(2));
+    }
+
+    #[test]
+    fn received_items_are_only_counted_once_delivered() {
+        let memory = counting_save();
+        let mut items_to_give = VecDeque::new();
+        let mut queued_through = None;
+        queue(&memory, &mut items_to_give, &mut queued_through, 0, vec![network_item(HAND_SCANNER, OTHER_PLAYER), network_item(CHAIN_WHIP, OTHER_PLAYER)]);
+        update_received_item_count(&memory, &items_to_give, queued_through);
+        assert_eq!(received_item_count(&memory), 0);
+
+        // The Hand Scanner's item get finishes and it leaves the queue
+        items_to_give.pop_front();
+        update_received_item_count(&memory, &items_to_give, queued_through);
+        assert_eq!(queued_items(&items_to_give), vec![CHAIN_WHIP]);
+        assert_eq!(received_item_count(&memory), 1);
+
+        // Quitting before the Chain Whip is delivered, so the reloaded save queues it again
+        let mut items_to_give = VecDeque::new();
+        queue(&memory, &mut items_to_give, &mut None, 0, vec![network_item(HAND_SCANNER, OTHER_PLAYER), network_item(CHAIN_WHIP, OTHER_PLAYER)]);
+        assert_eq!(queued_items(&items_to_give), vec![CHAIN_WHIP]);
+
+        items_to_give.clear();
+        update_received_item_count(&memory, &items_to_give, Some(2));
+        assert_eq!(received_item_count(&memory), 2);
+    }
+
+    #[test]
+    fn legacy_saves_resync_from_the_first_item_without_regranting() {
+        let memory = FakeGameMemory::new();
+        // Older launchers started the index at 1 and advanced it once per packet
+        set_received_item_count(&memory, 7);
+        memory.set_global_flag(0x96, 2);
+        let mut items_to_give = VecDeque::new();
+        let mut queued_through = None;
+        let network_items = vec![network_item(HAND_SCANNER, OTHER_PLAYER), network_item(SHELL_HORN, OTHER_PLAYER), network_item(CHAIN_WHIP, OTHER_PLAYER)];
+
+        assert!(queue(&memory, &mut items_to_give, &mut queued_through, 3, network_items.clone()));
+        assert!(!queue(&memory, &mut items_to_give, &mut queued_through, 0, network_items.clone()));
+        assert_eq!(queued_items(&items_to_give), vec![CHAIN_WHIP]);
+
+        // Still resyncing the same way until the Chain Whip is delivered
+        update_received_item_count(&memory, &items_to_give, queued_through);
+        assert_eq!(memory.global_flag(RECEIVED_ITEMS_COUNTED_FLAG), 0);
+        assert!(!queue(&memory, &mut items_to_give, &mut queued_through, 0, network_items));
+        assert_eq!(queued_items(&items_to_give), vec![CHAIN_WHIP]);
+
+        items_to_give.clear();
+        update_received_item_count(&memory, &items_to_give, queued_through);
+        assert_eq!(memory.global_flag(RECEIVED_ITEMS_COUNTED_FLAG), 1);
+        assert_eq!(received_item_count(&memory), 3);
+    }
+
+    #[test]
+    fn received_item_count_spans_both_flags() {
+        let memory = FakeGameMemory::new();
+        set_received_item_count(&memory, 0x1234);
+
+        assert_eq!(memory.global_flag(0x867), 0x12);
+        assert_eq!(memory.global_flag(0x868), 0x34);
+        assert_eq!(received_item_count(&memory), 0x1234);
+    }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+
+use crate::{FillerItem, ProgressiveItem};
+use crate::application::Application;
+use crate::lm_structs::items::{ARCHIPELAGO_ITEM_LOOKUP, Item};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Room {
+    pub field: u8,
+    pub scene: u8,
+    pub screen: u8
+}
+
+impl std::fmt::Display for Room {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.field, self.scene, self.screen)
+    }
+}
+
+// Everything the entrypoint logic needs from the running game, so that logic can be driven by FakeGameMemory instead of process memory
+pub trait GameMemory {
+    fn global_flag(&self, flag: usize) -> u8;
+    fn set_global_flag(&self, flag: usize, value: u8);
+    fn system_flag(&self, index: usize) -> u32;
+    fn game_initialized(&self) -> bool;
+    fn current_room(&self) -> Room;
+    fn inventory_word(&self, item_id: usize) -> u16;
+    fn set_inventory_word(&self, item_id: usize, value: u16);
+    fn spawn_item_get(&self, lm_item: &Item);
+    fn spawn_item_popup(&self, item_id: u32);
+    fn spawn_message_popup(&self, item_id: u32);
+}
+
+impl GameMemory for Application {
+    fn global_flag(&self, flag: usize) -> u8 {
+        let global_flags: &mut [u8;4096] = self.read_address("global_flags");
+        global_flags[flag]
+    }
+
+    fn set_global_flag(&self, flag: usize, value: u8) {
+        let global_flags: &mut [u8;4096] = self.read_address("global_flags");
+        global_flags[flag] = value;
+    }
+
+    fn system_flag(&self, index: usize) -> u32 {
+        let system_flags: &mut [u32;16] = self.read_address("system_flags");
+        system_flags[index]
+    }
+
+    fn game_initialized(&self) -> bool {
+        let game_init: &mut u32 = self.read_address("game_init");
+        *game_init != 0
+    }
+
+    fn current_room(&self) -> Room {
+        Room {
+            field: *self.read_address::<u8>("current_field"),
+            scene: *self.read_address::<u8>("current_scene"),
+            screen: *self.read_address::<u8>("current_screen")
+        }
+    }
+
+    fn inventory_word(&self, item_id: usize) -> u16 {
+        let inventory_words: &mut [u16;255] = self.read_address("inventory_words");
+        inventory_words[item_id]
+    }
+
+    fn set_inventory_word(&self, item_id: usize, value: u16) {
+        let inventory_words: &mut [u16;255] = self.read_address("inventory_words");
+        inventory_words[item_id] = value;
+    }
+
+    fn spawn_item_get(&self, lm_item: &Item) {
+        self.give_item(lm_item);
+    }
+
+    fn spawn_item_popup(&self, item_id: u32) {
+        self.create_dialog_popup(item_id);
+    }
+
+    fn spawn_message_popup(&self, item_id: u32) {
+        self.create_message_popup(item_id);
+    }
+}
+
+// Grants an item without the item get animation, stacking anything past the unique items
+pub fn grant_item(memory: &dyn GameMemory, lm_item: &Item) {
+    if lm_item.item_id > 104 {
+        memory.set_inventory_word(lm_item.item_id, memory.inventory_word(lm_item.item_id).saturating_add(1));
+    } else {
+        memory.set_inventory_word(lm_item.item_id, 1);
+    }
+    memory.set_global_flag(lm_item.flag, 2);
+}
+
+// Fillers only add to their inventory slot, so there is no flag or item get animation
+pub fn grant_filler(memory: &dyn GameMemory, filler_item: &FillerItem) {
+    let current_quantity = memory.inventory_word(filler_item.inventory_slot);
+    let new_quantity = current_quantity.saturating_add(filler_item.quantity).min(filler_item.max_quantity);
+    memory.set_inventory_word(filler_item.inventory_slot, new_quantity);
+}
+
+// The tier after however many are owned or waiting on their item get, None once every tier is owned
+pub fn next_progressive_tier(memory: &dyn GameMemory, progressive_item: &ProgressiveItem, in_flight_items: &[i64]) -> Option<i64> {
+    let owned_tiers = progressive_item.tiers.iter().filter(|tier| {
+        in_flight_items.contains(tier) || ARCHIPELAGO_ITEM_LOOKUP.get(tier).is_some_and(|lm_item| memory.global_flag(lm_item.flag) != 0)
+    }).count();
+    progressive_item.tiers.get(owned_tiers).copied()
+}
+
+pub fn ap_item_name(ap_item_id: i64, progressive_items: &HashMap<i64, ProgressiveItem>) -> Option<String> {
+    progressive_items.get(&ap_item_id).map(|progressive_item| progressive_item.item_name.clone())
+        .or_else(|| ARCHIPELAGO_ITEM_LOOKUP.get(&ap_item_id).map(|lm_item| lm_item.item_name.to_string()))
+}
+
+// In-memory stand in for the game. Spawned tasks are only recorded, nothing is animated
+#[cfg(test)]
+pub struct FakeGameMemory {
+    pub global_flags: RefCell<[u8;4096]>,
+    pub system_flags: RefCell<[u32;16]>,
+    pub game_init: Cell<bool>,
+    pub room: Cell<Room>,
+    pub inventory_words: RefCell<[u16;255]>,
+    pub spawned_items: RefCell<Vec<usize>>,
+    pub spawned_popups: RefCell<Vec<u32>>,
+    pub spawned_messages: RefCell<Vec<u32>>
+}
+
+#[cfg(test)]
+impl FakeGameMemory {
+    pub fn new() -> FakeGameMemory {
+        FakeGameMemory {
+            global_flags: RefCell::new([0;4096]),
+            system_flags: RefCell::new([0;16]),
+            game_init: Cell::new(true),
+            room: Cell::new(Room::default()),
+            inventory_words: RefCell::new([0;255]),
+            spawned_items: RefCell::new(Vec::new()),
+            spawned_popups: RefCell::new(Vec::new()),
+            spawned_messages: RefCell::new(Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+impl Default for FakeGameMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl GameMemory for FakeGameMemory {
+    fn global_flag(&self, flag: usize) -> u8 {
+        self.global_flags.borrow()[flag]
+    }
+
+    fn set_global_flag(&self, flag: usize, value: u8) {
+        self.global_flags.borrow_mut()[flag] = value;
+    }
+
+    fn system_flag(&self, index: usize) -> u32 {
+        self.system_flags.borrow()[index]
+    }
+
+    fn game_initialized(&self) -> bool {
+        self.game_init.get()
+    }
+
+    fn current_room(&self) -> Room {
+        self.room.get()
+    }
+
+    fn inventory_word(&self, item_id: usize) -> u16 {
+        self.inventory_words.borrow()[item_id]
+    }
+
+    fn set_inventory_word(&self, item_id: usize, value: u16) {
+        self.inventory_words.borrow_mut()[item_id] = value;
+    }
+
+    // The real item get task grants the item once the animation finishes, which happens immediately here
+    fn spawn_item_get(&self, lm_item: &Item) {
+        self.spawned_items.borrow_mut().push(lm_item.item_id);
+        grant_item(self, lm_item);
+    }
+
+    fn spawn_item_popup(&self, item_id: u32) {
+        self.spawned_popups.borrow_mut().push(item_id);
+    }
+
+    fn spawn_message_popup(&self, item_id: u32) {
+        self.spawned_messages.borrow_mut().push(item_id);
+    }
+}
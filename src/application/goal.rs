@@ -0,0 +1,169 @@
+use archipelago_api::api::PrintJSON;
+use log::info;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::AppConfig;
+use crate::application::entrypoints::queue_message_popup;
+use crate::application::game_memory::GameMemory;
+
+const GUARDIANS_KILLED_FLAG: usize = 0x102;
+const ANKH_JEWEL_FLAGS: [usize; 9] = [0x8e, 0x8f, 0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x853];
+// Same check the game loop used before goals were tracked, the escape sets this bit and keeps it through the credits
+const ESCAPE_SYSTEM_FLAG: u32 = 0x20000;
+const NOTICE_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    Escape,
+    Guardians(u8),
+    AnkhJewels,
+    // Flag of the item placed at the Hell Temple reward, missing if the slot has no such location
+    HellTemple(Option<u16>)
+}
+
+impl Goal {
+    // Configs without a goal predate goal variants, so they keep escaping La-Mulana as the goal
+    pub fn from_config(app_config: &AppConfig) -> Goal {
+        match app_config.goal.as_str() {
+            "guardians" => Goal::Guardians(app_config.goal_guardians),
+            "ankh_jewels" => Goal::AnkhJewels,
+            "hell_temple" => Goal::HellTemple(app_config.hell_temple_flag),
+            _ => Goal::Escape
+        }
+    }
+
+    pub fn is_complete(&self, memory: &dyn GameMemory) -> bool {
+        match self {
+            Goal::Escape => escape_started(memory),
+            Goal::Guardians(guardians) => memory.global_flag(GUARDIANS_KILLED_FLAG) >= *guardians,
+            Goal::AnkhJewels => ANKH_JEWEL_FLAGS.iter().all(|flag| memory.global_flag(*flag) > 0),
+            Goal::HellTemple(Some(flag)) => memory.global_flag(*flag as usize) > 0,
+            Goal::HellTemple(None) => false
+        }
+    }
+}
+
+// The escape sequence and credits run outside of the save, so it is checked before anything else in the game loop
+pub fn escape_started(memory: &dyn GameMemory) -> bool {
+    (memory.system_flag(3) & ESCAPE_SYSTEM_FLAG) == ESCAPE_SYSTEM_FLAG
+}
+
+struct GoalState {
+    reached: bool,
+    acknowledged: bool,
+    last_notice: Option<Instant>
+}
+
+static GOAL_STATE: Mutex<GoalState> = Mutex::new(GoalState { reached: false, acknowledged: false, last_notice: None });
+
+// Latched for the rest of the session, leaving the save doesn't take the goal back
+pub fn check_goal(memory: &dyn GameMemory, goal: &Goal) {
+    let Ok(mut goal_state) = GOAL_STATE.try_lock() else { return };
+    if goal_state.reached || !goal.is_complete(memory) {
+        return;
+    }
+
+    info!("Goal {:?} reached", goal);
+    goal_state.reached = true;
+    queue_message_popup(vec!["Goal complete!".to_string()]);
+}
+
+pub fn goal_reached() -> bool {
+    GOAL_STATE.lock().unwrap().reached
+}
+
+// A status can be lost without an error, so the goal is resent on an interval until the server announces it
+pub fn acknowledgement_pending() -> bool {
+    let goal_state = GOAL_STATE.lock().unwrap();
+    goal_state.reached && !goal_state.acknowledged
+}
+
+pub fn notice_due() -> bool {
+    let goal_state = GOAL_STATE.lock().unwrap();
+    let due = goal_state.last_notice.is_none_or(|last_notice| last_notice.elapsed() >= NOTICE_RETRY_INTERVAL);
+    goal_state.reached && !goal_state.acknowledged && due
+}
+
+pub fn record_notice() {
+    GOAL_STATE.lock().unwrap().last_notice = Some(Instant::now());
+}
+
+// A failed send is retried straight away instead of waiting out the interval
+pub fn notice_failed() {
+    GOAL_STATE.lock().unwrap().last_notice = None;
+}
+
+pub fn on_print_json(print_json: &PrintJSON, local_slot: i64) {
+    if print_json.r#type.as_deref() == Some("Goal") && print_json.slot == Some(local_slot) {
+        info!("Server acknowledged goal completion");
+        GOAL_STATE.lock().unwrap().acknowledged = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::game_memory::FakeGameMemory;
+
+    #[test]
+    fn escape_goal_completes_once_the_escape_flag_is_set() {
+        let memory = FakeGameMemory::new();
+        memory.system_flags.borrow_mut()[3] = 0x1;
+        assert!(!Goal::Escape.is_complete(&memory));
+
+        memory.system_flags.borrow_mut()[3] |= ESCAPE_SYSTEM_FLAG;
+        assert!(Goal::Escape.is_complete(&memory));
+    }
+
+    #[test]
+    fn guardian_goal_needs_the_configured_number_of_guardians() {
+        let memory = FakeGameMemory::new();
+        memory.global_flags.borrow_mut()[GUARDIANS_KILLED_FLAG] = 4;
+        assert!(Goal::Guardians(4).is_complete(&memory));
+        assert!(!Goal::Guardians(5).is_complete(&memory));
+    }
+
+    #[test]
+    fn ankh_jewel_goal_needs_every_jewel() {
+        let memory = FakeGameMemory::new();
+        for flag in &ANKH_JEWEL_FLAGS[1..] {
+            memory.global_flags.borrow_mut()[*flag] = 1;
+        }
+        assert!(!Goal::AnkhJewels.is_complete(&memory));
+
+        memory.global_flags.borrow_mut()[ANKH_JEWEL_FLAGS[0]] = 1;
+        assert!(Goal::AnkhJewels.is_complete(&memory));
+    }
+
+    #[test]
+    fn hell_temple_goal_waits_for_the_reward_flag() {
+        let memory = FakeGameMemory::new();
+        assert!(!Goal::HellTemple(Some(0x900)).is_complete(&memory));
+        assert!(!Goal::HellTemple(None).is_complete(&memory));
+
+        memory.global_flags.borrow_mut()[0x900] = 2;
+        assert!(Goal::HellTemple(Some(0x900)).is_complete(&memory));
+    }
+
+    #[test]
+    fn goal_notice_repeats_until_the_server_announces_the_goal() {
+        let memory = FakeGameMemory::new();
+        memory.global_flags.borrow_mut()[GUARDIANS_KILLED_FLAG] = 8;
+        check_goal(&memory, &Goal::Guardians(8));
+        assert!(notice_due());
+
+        record_notice();
+        assert!(!notice_due());
+        notice_failed();
+        assert!(notice_due());
+
+        let other_slot_goal = PrintJSON { data: Vec::new(), r#type: Some("Goal".to_string()), receiving: None, item: None, found: None, team: None, slot: Some(2), message: None, tags: None, countdown: None };
+        on_print_json(&other_slot_goal, 1);
+        assert!(acknowledgement_pending());
+
+        on_print_json(&PrintJSON { slot: Some(1), ..other_slot_goal }, 1);
+        assert!(!acknowledgement_pending());
+        assert!(!notice_due());
+    }
+}
@@ -0,0 +1,95 @@
+use log::error;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::get_application;
+use crate::utils::show_message_box;
+
+static FRAME: AtomicU64 = AtomicU64::new(0);
+static FAILURE_REPORTED: AtomicBool = AtomicBool::new(false);
+static DISABLED_FEATURES: [AtomicBool; 5] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false)
+];
+
+// Each detour's added behaviour, which is switched off on its own after a failure so the rest of the randomizer keeps working
+#[derive(Clone, Copy, Debug)]
+pub enum Feature {
+    GameLoop,
+    PopupText,
+    ItemSymbolInit,
+    ItemSymbolBack,
+    ItemFlag
+}
+
+impl Feature {
+    fn is_disabled(&self) -> bool {
+        DISABLED_FEATURES[*self as usize].load(Ordering::Relaxed)
+    }
+
+    fn disable(&self) {
+        DISABLED_FEATURES[*self as usize].store(true, Ordering::Relaxed);
+    }
+}
+
+// The default hook writes to stderr, which the game doesn't have, so panics are routed to the log instead
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|panic_info| {
+        let location = panic_info.location().map(|location| location.to_string()).unwrap_or("unknown location".to_string());
+        error!("Panic at {}: {}", location, panic_message(panic_info.payload()));
+    }));
+}
+
+pub fn advance_frame() {
+    FRAME.fetch_add(1, Ordering::Relaxed);
+}
+
+// Runs the added behaviour of a detour, catching any panic so it never unwinds into the game.
+// The caller is still responsible for calling the original game function afterwards
+pub fn guard<F: FnOnce()>(feature: Feature, context: impl FnOnce() -> String, body: F) {
+    if feature.is_disabled() {
+        return;
+    }
+
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(body)) {
+        feature.disable();
+        error!(
+            "{:?} failed on frame {} in room {} ({}): {}. It has been disabled for the rest of the session",
+            feature,
+            FRAME.load(Ordering::Relaxed),
+            current_room(),
+            context(),
+            panic_message(payload.as_ref())
+        );
+
+        if !FAILURE_REPORTED.swap(true, Ordering::Relaxed) {
+            let message = format!("The randomizer hit an error in {:?} and has disabled it for the rest of this session. Please check the log and report the issue.", feature);
+            // Message boxes block, so keep it off the game thread
+            std::thread::spawn(move || show_message_box(&message));
+        }
+    }
+}
+
+fn current_room() -> String {
+    panic::catch_unwind(|| {
+        let application = get_application();
+        let field: &mut u8 = application.read_address("current_field");
+        let scene: &mut u8 = application.read_address("current_scene");
+        let screen: &mut u8 = application.read_address("current_screen");
+        format!("{},{},{}", field, scene, screen)
+    }).unwrap_or("unknown".to_string())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
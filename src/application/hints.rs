@@ -0,0 +1,131 @@
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use winapi::um::processthreadsapi::GetCurrentThreadId;
+use winapi::um::winuser::{GetAsyncKeyState, GetForegroundWindow, GetWindowThreadProcessId};
+
+use crate::ProgressiveItem;
+use crate::application::game_memory::{GameMemory, ap_item_name, next_progressive_tier};
+use crate::lm_structs::items::ARCHIPELAGO_ITEM_LOOKUP;
+
+// Everything needed to work out whether a hint is affordable, which arrives across RoomInfo, Connected and RoomUpdate
+struct HintState {
+    hint_points: i64,
+    hint_cost_percent: i64,
+    location_count: i64,
+    requested: bool,
+    awaiting_answer: bool,
+    hinted_items: HashSet<i64>
+}
+
+static HINT_STATE: LazyLock<Mutex<HintState>> = LazyLock::new(|| {
+    Mutex::new(HintState {
+        hint_points: 0,
+        hint_cost_percent: 0,
+        location_count: 0,
+        requested: false,
+        awaiting_answer: false,
+        hinted_items: HashSet::new()
+    })
+});
+static HOTKEY_HELD: AtomicBool = AtomicBool::new(false);
+
+pub fn set_hint_cost(hint_cost_percent: i64) {
+    HINT_STATE.lock().unwrap().hint_cost_percent = hint_cost_percent;
+}
+
+pub fn set_hint_points(hint_points: i64) {
+    HINT_STATE.lock().unwrap().hint_points = hint_points;
+}
+
+pub fn set_location_count(location_count: i64) {
+    HINT_STATE.lock().unwrap().location_count = location_count;
+}
+
+// Mirrors the server, which charges a percentage of the slot's locations with a minimum of one point
+fn hint_cost(hint_state: &HintState) -> i64 {
+    std::cmp::max(1, hint_state.hint_cost_percent * hint_state.location_count / 100)
+}
+
+// Only reacts to the key going down while the game's window has focus, so holding it or typing in another window does nothing.
+// Must be called from the game loop, whose thread owns the game's window
+pub fn poll_hint_hotkey(hotkey: &str) {
+    let Some(virtual_key) = virtual_key_code(hotkey) else { return };
+
+    let key_down = unsafe { (GetAsyncKeyState(virtual_key) as u16 & 0x8000) != 0 };
+    let was_held = HOTKEY_HELD.swap(key_down, Ordering::Relaxed);
+    if key_down && !was_held && game_has_focus() {
+        HINT_STATE.lock().unwrap().requested = true;
+    }
+}
+
+// Compared by thread rather than process, so the DLL's own message boxes and any console don't count as the game
+fn game_has_focus() -> bool {
+    unsafe {
+        let foreground_window = GetForegroundWindow();
+        !foreground_window.is_null() && GetWindowThreadProcessId(foreground_window, std::ptr::null_mut()) == GetCurrentThreadId()
+    }
+}
+
+fn virtual_key_code(hotkey: &str) -> Option<i32> {
+    let hotkey = hotkey.to_uppercase();
+    if let Some(function_key) = hotkey.strip_prefix('F').and_then(|number| number.parse::<i32>().ok()) {
+        return (1..=12).contains(&function_key).then(|| 0x6f + function_key);
+    }
+
+    let mut characters = hotkey.chars();
+    match (characters.next(), characters.next()) {
+        (Some(character), None) if character.is_ascii_alphanumeric() => Some(character as i32),
+        _ => None
+    }
+}
+
+pub enum HintRequest {
+    Send(String),
+    NotEnoughPoints(i64, i64),
+    NothingToHint
+}
+
+// Picks the first progression item of this slot that hasn't been received or already hinted this session
+pub fn take_hint_request(memory: &dyn GameMemory, hint_items: &[i64], progressive_items: &HashMap<i64, ProgressiveItem>) -> Option<HintRequest> {
+    let mut hint_state = HINT_STATE.lock().unwrap();
+    if !hint_state.requested {
+        return None;
+    }
+    hint_state.requested = false;
+
+    let cost = hint_cost(&hint_state);
+    if hint_state.hint_points < cost {
+        return Some(HintRequest::NotEnoughPoints(hint_state.hint_points, cost));
+    }
+
+    let next_item = hint_items.iter().find(|item_id| {
+        let lm_item = match progressive_items.get(*item_id) {
+            Some(progressive_item) => next_progressive_tier(memory, progressive_item, &[]).and_then(|tier| ARCHIPELAGO_ITEM_LOOKUP.get(&tier)),
+            None => ARCHIPELAGO_ITEM_LOOKUP.get(*item_id)
+        };
+        !hint_state.hinted_items.contains(*item_id) && lm_item.is_some_and(|lm_item| memory.global_flag(lm_item.flag) == 0)
+    });
+
+    match next_item {
+        Some(item_id) => {
+            let item_name = ap_item_name(*item_id, progressive_items).unwrap();
+            debug!("Requesting hint for {}", item_name);
+            hint_state.hinted_items.insert(*item_id);
+            hint_state.awaiting_answer = true;
+            Some(HintRequest::Send(item_name))
+        },
+        None => Some(HintRequest::NothingToHint)
+    }
+}
+
+// The answer to a hint request is the next hint or command result from the server
+pub fn take_hint_answer(message_type: &str) -> bool {
+    let mut hint_state = HINT_STATE.lock().unwrap();
+    let is_answer = hint_state.awaiting_answer && (message_type == "Hint" || message_type == "CommandResult");
+    if is_answer {
+        hint_state.awaiting_answer = false;
+    }
+    is_answer
+}
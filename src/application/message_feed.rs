@@ -0,0 +1,155 @@
+use archipelago_api::api::{Connected, DataPackage, JSONMessagePart, LocationInfo, NetworkItem, PrintJSON};
+use log::debug;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::application::entrypoints::queue_message_popup;
+use crate::application::hints;
+
+const MAX_LINE_LENGTH: usize = 30;
+const MAX_LINES: usize = 4;
+
+// Names needed to render message parts, which only carry slot, item and location ids
+struct MessageFeed {
+    local_slot: i64,
+    slot_names: HashMap<i64, String>,
+    slot_games: HashMap<i64, String>,
+    item_names: HashMap<String, HashMap<i64, String>>,
+    location_names: HashMap<String, HashMap<i64, String>>
+}
+
+static MESSAGE_FEED: LazyLock<Mutex<MessageFeed>> = LazyLock::new(|| {
+    Mutex::new(MessageFeed {
+        local_slot: 0,
+        slot_names: HashMap::new(),
+        slot_games: HashMap::new(),
+        item_names: HashMap::new(),
+        location_names: HashMap::new()
+    })
+});
+
+// Returns the games whose data package is needed to name their items and locations
+pub fn on_connected(connected: &Connected) -> Vec<String> {
+    let mut message_feed = MESSAGE_FEED.lock().unwrap();
+    message_feed.local_slot = connected.slot;
+
+    for (slot, network_slot) in &connected.slot_info {
+        if let Ok(slot) = slot.parse::<i64>() {
+            message_feed.slot_names.insert(slot, network_slot.name.clone());
+            message_feed.slot_games.insert(slot, network_slot.game.clone());
+        }
+    }
+
+    let mut games = message_feed.slot_games.values().cloned().collect::<Vec<String>>();
+    games.sort();
+    games.dedup();
+    games
+}
+
+pub fn on_data_package(data_package: DataPackage) {
+    let mut message_feed = MESSAGE_FEED.lock().unwrap();
+    for (game, game_data) in data_package.data.games {
+        let item_names = game_data.item_name_to_id.into_iter().map(|(name, id)| (id, name)).collect::<HashMap<_,_>>();
+        let location_names = game_data.location_name_to_id.into_iter().map(|(name, id)| (id, name)).collect::<HashMap<_,_>>();
+        message_feed.item_names.insert(game.clone(), item_names);
+        message_feed.location_names.insert(game, location_names);
+    }
+}
+
+pub fn on_print_json(print_json: &PrintJSON, enabled_types: &[String], coin_chests: &HashMap<i64, i16>) {
+    let message_feed = MESSAGE_FEED.lock().unwrap();
+    let message_type = print_json.r#type.clone().unwrap_or("Text".to_string());
+
+    // Answers to our own hint requests are always shown, even if that message type is turned off
+    let hint_answer = hints::take_hint_answer(&message_type);
+    if !hint_answer && (!enabled_types.contains(&message_type) || !message_feed.involves_local_slot(&message_type, print_json)) {
+        return;
+    }
+
+    let coin_chest_name = print_json.item.as_ref().and_then(|item| message_feed.coin_chest_name(item, coin_chests));
+    let message = print_json.data.iter().map(|part| match (part.r#type.as_deref(), &coin_chest_name) {
+        (Some("item_id"), Some(coin_chest_name)) => coin_chest_name.clone(),
+        _ => message_feed.render_part(part)
+    }).collect::<String>();
+    debug!("Queueing {} message: {}", message_type, message);
+    queue_message_popup(wrap_lines(&message));
+}
+
+pub fn on_location_info(location_info: &LocationInfo, coin_chests: &HashMap<i64, i16>) {
+    let message_feed = MESSAGE_FEED.lock().unwrap();
+    let lines = location_info.location.iter().map(|network_item| {
+        let item_name = message_feed.coin_chest_name(network_item, coin_chests)
+            .or_else(|| message_feed.lookup_name(&message_feed.item_names, Some(network_item.player), network_item.item))
+            .unwrap_or(network_item.item.to_string());
+        let player_name = message_feed.slot_names.get(&network_item.player).cloned().unwrap_or(network_item.player.to_string());
+        format!("{} for {}", item_name, player_name)
+    }).collect::<Vec<String>>();
+
+    if !lines.is_empty() {
+        queue_message_popup(lines);
+    }
+}
+
+impl MessageFeed {
+    // Item related messages are only shown when this slot sent or receives the item, everything else is shown to everyone
+    fn involves_local_slot(&self, message_type: &str, print_json: &PrintJSON) -> bool {
+        match message_type {
+            "ItemSend" | "ItemCheat" | "Hint" => {
+                let finder = print_json.item.as_ref().map(|item| item.player);
+                print_json.receiving == Some(self.local_slot) || finder == Some(self.local_slot)
+            },
+            _ => true
+        }
+    }
+
+    // The server only knows this slot's coin chests as Shell Horns, so they are named after the gold inside
+    fn coin_chest_name(&self, network_item: &NetworkItem, coin_chests: &HashMap<i64, i16>) -> Option<String> {
+        if network_item.player != self.local_slot {
+            return None;
+        }
+        coin_chests.get(&network_item.location).map(|quantity| format!("{} Coins", quantity))
+    }
+
+    fn render_part(&self, part: &JSONMessagePart) -> String {
+        let text = part.text.clone().unwrap_or_default();
+        let id = text.parse::<i64>().ok();
+
+        match (part.r#type.as_deref(), id) {
+            (Some("player_id"), Some(slot)) => self.slot_names.get(&slot).cloned().unwrap_or(text),
+            (Some("item_id"), Some(item_id)) => self.lookup_name(&self.item_names, part.player, item_id).unwrap_or(text),
+            (Some("location_id"), Some(location_id)) => self.lookup_name(&self.location_names, part.player, location_id).unwrap_or(text),
+            _ => text
+        }
+    }
+
+    fn lookup_name(&self, names: &HashMap<String, HashMap<i64, String>>, slot: Option<i64>, id: i64) -> Option<String> {
+        let game = self.slot_games.get(&slot?)?;
+        names.get(game)?.get(&id).cloned()
+    }
+}
+
+// Breaks a message into popup sized lines on word boundaries, cutting it off with an ellipsis if it runs too long
+fn wrap_lines(message: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_line = String::new();
+
+    for word in message.split_whitespace() {
+        if !current_line.is_empty() && current_line.chars().count() + word.chars().count() + 1 > MAX_LINE_LENGTH {
+            lines.push(current_line);
+            current_line = String::new();
+        }
+        if !current_line.is_empty() {
+            current_line.push(' ');
+        }
+        current_line.push_str(word);
+    }
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES);
+        lines.push("...".to_string());
+    }
+    lines
+}
@@ -1,12 +1,21 @@
 pub mod entrypoints;
+pub mod game_memory;
+pub mod goal;
+pub mod guard;
+pub mod hints;
+pub mod message_feed;
+pub mod session;
+pub mod shops;
+pub mod signatures;
+pub mod text_arena;
 
 use archipelago_api::api::APError;
 use archipelago_api::client::APClient;
-use log::{debug, error, trace};
+use game_versions::GameVersion;
+use log::{debug, error, trace, warn};
 use retour::{Function, static_detour, StaticDetour};
-use std::collections::HashMap;
 use std::ffi::c_void;
-use std::sync::{LazyLock, Mutex};
+use std::sync::Mutex;
 use windows::Win32::System::Memory::{VirtualProtect, PAGE_EXECUTE_READWRITE, PAGE_PROTECTION_FLAGS};
 
 use crate::AppConfig;
@@ -39,17 +48,18 @@ pub struct Application {
     pub address: usize,
     pub randomizer: Mutex<Result<APClient, APError>>,
     pub app_config: AppConfig,
-    pub app_version: String
+    pub app_version: String,
+    pub game_version: Result<GameVersion, String>
 }
 
 impl Application {
     pub fn attach(&self) {
-        let version = self.application_version();
-
-        if let Some(_) = Application::ADDRESS_LOOKUP.get(version) {
+        if let Err(error_message) = &self.game_version {
+            show_message_box(error_message);
+        }
+        else {
             unsafe {
-                self.patch_shop_sacred_orb();
-                self.patch_weapon_swap_crash();
+                self.apply_patches();
 
                 let game_loop_addr: FnGameLoop = std::mem::transmute(self.extract_offset("game_loop"));
                 let _ = self.enable_detour(GameLoopDetour.initialize(game_loop_addr, game_loop), "GameLoopDetour");
@@ -63,10 +73,6 @@ impl Application {
                 trace!("Enabled all detours.");
             }
         }
-        else {
-            let error_message = format!("Unsupported version {}.", version);
-            show_message_box(&error_message);
-        }
     }
 
     fn get_address(&self) -> usize {
@@ -123,6 +129,16 @@ impl Application {
         trace!("Played sound effect for item ID {}", item_id);
     }
 
+    // Same popup without the item get pose or pausing, so status messages don't interrupt play
+    fn create_message_popup(&self, item_id: u32) {
+        self.option_stuck(item_id);
+        let popup_dialog_init: *const usize = self.read_address("popup_dialog_init");
+        let set_task: &*const () = self.read_address("set_view_event_ns");
+        let set_task_func: extern "C" fn(u16, *const usize) -> *const TaskData = unsafe { std::mem::transmute(set_task) };
+        (set_task_func)(16, popup_dialog_init);
+        trace!("Called popup_dialog_init for message with item ID {}", item_id);
+    }
+
     fn popup_dialog_draw(&self, popup_dialog: &'static TaskData) {
         PopupDialogDrawInterceptDetour.call(popup_dialog)
     }
@@ -203,37 +219,19 @@ impl Application {
         }
     }
 
-    unsafe fn patch_shop_sacred_orb(&self) {
-        let target_address = self.read_address("shop_orb_patch");
-
-        let new_instructions = HashMap::from([
-            ("1.0.0.1", [0x74, 0x27, 0x83, 0xf8, 0x45, 0x74, 0x22, 0xeb, 0x1b, 0xb8, 0x02, 0x00]),
-            ("1.6.6.1", [0x74, 0x23, 0x83, 0xf8, 0x45, 0x74, 0x1e, 0xeb, 0x17, 0x0f, 0x1f, 0x40]),
-            ("1.6.6.2", [0x74, 0x23, 0x83, 0xf8, 0x45, 0x74, 0x1e, 0xeb, 0x17, 0x02, 0x00, 0x00])
-        ]).get(self.application_version()).unwrap().clone();
-
-        self.patch_exe(target_address, new_instructions);
-    }
-
-    unsafe fn patch_weapon_swap_crash(&self) {
-        let target_address = self.read_address("weapon_swap_patch1");
-        let new_instructions = HashMap::from([
-            ("1.0.0.1", [0x0f, 0xbe, 0x46, 0x06, 0xb2, 0x05, 0x40, 0x83, 0xf8, 0x04, 0x7e, 0x02, 0x31, 0xc0, 0x8a, 0x0c, 0x30, 0xfe, 0xca, 0x74, 0x04, 0x38, 0xd9, 0x74, 0xed, 0x88, 0x4e, 0x05, 0xeb, 0x36, 0x61]),
-            ("1.6.6.1", [0x0f, 0xbe, 0x46, 0x06, 0xb2, 0x05, 0x40, 0x83, 0xf8, 0x04, 0x7e, 0x02, 0x31, 0xc0, 0x8a, 0x0c, 0x30, 0xfe, 0xca, 0x74, 0x04, 0x38, 0xd9, 0x74, 0xed, 0x88, 0x8e, 0x05, 0x00, 0x00, 0x00]),
-            ("1.6.6.2", [0x0f, 0xbe, 0x46, 0x06, 0xb2, 0x05, 0x40, 0x83, 0xf8, 0x04, 0x7e, 0x02, 0x31, 0xc0, 0x8a, 0x0c, 0x30, 0xfe, 0xca, 0x74, 0x04, 0x38, 0xd9, 0x74, 0xed, 0x88, 0x4e, 0x05, 0xeb, 0x2a, 0x6a])
-        ]).get(self.application_version()).unwrap().clone();
-        self.patch_exe(target_address, new_instructions);
-
-        let target_address = self.read_address("weapon_swap_patch2");
-        let new_instructions = HashMap::from([
-            ("1.0.0.1", [0x0f, 0xbe, 0x46, 0x06, 0xb1, 0x05, 0x0f, 0x1f, 0x40, 0x00, 0x48, 0x79, 0x05, 0xb8, 0x04, 0x00, 0x00, 0x00, 0xfe, 0xc9, 0x74, 0x05, 0x38, 0x1c, 0x30, 0x74, 0xef, 0x8a, 0x14, 0x30]),
-            ("1.6.6.1", [0x0f, 0xbe, 0x46, 0x06, 0xb1, 0x05, 0x83, 0xe8, 0x01, 0x79, 0x03, 0x83, 0xc0, 0x05, 0x8a, 0x14, 0x30, 0xfe, 0xc9, 0x74, 0x04, 0x38, 0xda, 0x74, 0xed, 0x88, 0x56, 0x05, 0xb9, 0x0f]),
-            ("1.6.6.2", [0x0f, 0xbe, 0x46, 0x06, 0xb1, 0x05, 0x48, 0x79, 0x03, 0x83, 0xc0, 0x05, 0x8a, 0x14, 0x30, 0xfe, 0xc9, 0x74, 0x04, 0x38, 0xda, 0x74, 0xef, 0x88, 0x56, 0x05, 0xb9, 0x0f, 0x00, 0x00])
-        ]).get(self.application_version()).unwrap().clone();
-        self.patch_exe(target_address, new_instructions);
+    // Byte patches are version specific, so versions resolved only by signature scan won't have any
+    unsafe fn apply_patches(&self) {
+        let patches = &self.game_version.as_ref().unwrap().patches;
+        if patches.is_empty() {
+            warn!("No patches for version {}, skipping", self.application_version());
+        }
+        for (patch_name, new_instructions) in patches {
+            self.patch_exe(self.read_address(patch_name), new_instructions);
+            trace!("Applied patch {}", patch_name);
+        }
     }
 
-    unsafe fn patch_exe<const N: usize>(&self, target_address: *mut u8, new_instructions: [u8;N]) {
+    unsafe fn patch_exe(&self, target_address: *mut u8, new_instructions: &[u8]) {
         let mut old_protect: PAGE_PROTECTION_FLAGS = PAGE_PROTECTION_FLAGS(0);
 
         VirtualProtect(
@@ -274,105 +272,8 @@ impl Application {
     }
 
     fn extract_offset(&self, offset_name: &str) -> usize {
-        let version = self.application_version();
-        let addresses = Application::ADDRESS_LOOKUP;
-        let address = addresses.get(version).unwrap();
-        let offset = address.get(&offset_name).unwrap();
+        let addresses = &self.game_version.as_ref().unwrap().symbols;
+        let offset = addresses.get(offset_name).unwrap();
         self.get_address().wrapping_add(*offset)
     }
-
-    pub const ADDRESS_LOOKUP: LazyLock<HashMap<&str, HashMap<&str, usize>>> = LazyLock::new(|| {
-        let version_1_0_0_1 = HashMap::from([
-            ("set_se",                0x00417600),
-            ("default_final",         0x00476cc0),
-            ("item_get_area_init",    0x004b8950),
-            ("item_symbol_init",      0x004b8ae0),
-            ("item_symbol_back",      0x004b8e70),
-            ("set_view_event_ns",     0x00507160),
-            ("popup_dialog_init",     0x00591520),
-            ("popup_dialog_draw",     0x005917b0),
-            ("shop_orb_patch",        0x005c406b),
-            ("game_loop",             0x00607b70),
-            ("weapon_swap_patch1",    0x00679323),
-            ("weapon_swap_patch2",    0x00679356),
-            ("se",                    0x006d2708),
-            ("script_header_pointer", 0x006d296c),
-            ("inventory_words",       0x006d5650),
-            ("system_flags",          0x006d59c0),
-            ("global_flags",          0x006d5a70),
-            ("current_scene",         0x00db4bb3),
-            ("current_screen",        0x00db4bb6),
-            ("current_field",         0x00db4bb7),
-            ("option_sdata_num",      0x00db6fb7),
-            ("option_sdata",          0x00db7048),
-            ("option_pos_cx",         0x00db714c),
-            ("option_pos_cy",         0x00db7168),
-            ("game_process",          0x00db7178),
-            ("lemeza_pointer",        0x00db7538),
-            ("game_init",             0x00db753c)
-        ]);
-        let version_1_6_6_1 = HashMap::from([
-            ("set_se",                0x00417670),
-            ("item_get_area_init",    0x004b9250),
-            ("item_symbol_init",      0x004b93e0),
-            ("item_symbol_back",      0x004b9770),
-            ("set_view_event_ns",     0x00507be0),
-            ("popup_dialog_init",     0x00591ae0),
-            ("popup_dialog_draw",     0x00591d70),
-            ("shop_orb_patch",        0x005c49da),
-            ("game_loop",             0x00608670),
-            ("default_final",         0x00473450),
-            ("weapon_swap_patch1",    0x0067a0ea),
-            ("weapon_swap_patch2",    0x0067a11b),
-            ("se",                    0x006d4708),
-            ("script_header_pointer", 0x006d49f0),
-            ("inventory_words",       0x006d76e4),
-            ("system_flags",          0x006d7a40),
-            ("global_flags",          0x006d7d08),
-            ("current_scene",         0x00db6d7a),
-            ("current_screen",        0x00db6d7b),
-            ("current_field",         0x00db6d7e),
-            ("option_sdata_num",      0x00db917e),
-            ("option_sdata",          0x00db9210),
-            ("option_pos_cx",         0x00db9314),
-            ("option_pos_cy",         0x00db9330),
-            ("game_process",          0x00db9340),
-            ("lemeza_pointer",        0x00db9700),
-            ("game_init",             0x00db9704)
-        ]);
-        let version_1_6_6_2 = HashMap::from([
-            ("set_se",                0x004186c0),
-            ("item_get_area_init",    0x004ba720),
-            ("item_symbol_init",      0x004ba8b0),
-            ("item_symbol_back",      0x004bac40),
-            ("set_view_event_ns",     0x00509530),
-            ("popup_dialog_init",     0x00593670),
-            ("popup_dialog_draw",     0x00593900),
-            ("shop_orb_patch",        0x005c676a),
-            ("game_loop",             0x00609fb0),
-            ("default_final",         0x00620950),
-            ("weapon_swap_patch1",    0x0067b435),
-            ("weapon_swap_patch2",    0x0067b463),
-            ("se",                    0x006de844),
-            ("script_header_pointer", 0x006deb2c),
-            ("inventory_words",       0x006e1820),
-            ("system_flags",          0x006e1b90),
-            ("global_flags",          0x006e1e48),
-            ("current_scene",         0x00dc0ebe),
-            ("current_screen",        0x00dc0ebf),
-            ("current_field",         0x00dc0ee6),
-            ("option_sdata_num",      0x00dc32c2),
-            ("option_sdata",          0x00dc3350),
-            ("option_pos_cx",         0x00dc3454),
-            ("option_pos_cy",         0x00dc3470),
-            ("game_process",          0x00dc3480),
-            ("lemeza_pointer",        0x00dc3844),
-            ("game_init",             0x00dc3848)
-        ]);
-        HashMap::from([
-            ("1.0.0.1", version_1_0_0_1),
-            ("1.6.6.1", version_1_6_6_1),
-            ("1.6.6.2", version_1_6_6_2)
-        ])
-    });
 }
@@ -0,0 +1,116 @@
+use archipelago_api::api::ClientStatus;
+use log::info;
+use std::sync::Mutex;
+
+use crate::application::entrypoints::{queue_message_popup, randomizer_save_loaded};
+use crate::application::game_memory::{GameMemory, Room};
+use crate::application::goal;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Refused(Vec<String>)
+}
+
+struct Session {
+    state: ConnectionState,
+    // The server drops refused connections, so the same refusal comes back on every reconnect attempt
+    last_refusal: Option<Vec<String>>,
+    client_status: ClientStatus,
+    // Every new connection starts without a status, so it is reported again after reconnecting
+    reported_status: Option<ClientStatus>,
+    start_room: Option<Room>
+}
+
+static SESSION: Mutex<Session> = Mutex::new(Session {
+    state: ConnectionState::Disconnected,
+    last_refusal: None,
+    client_status: ClientStatus::ClientConnected,
+    reported_status: None,
+    start_room: None
+});
+
+pub fn set_connection_state(state: ConnectionState) {
+    let mut session = SESSION.lock().unwrap();
+    if session.state == state {
+        return;
+    }
+    info!("Connection state changed from {:?} to {:?}", session.state, state);
+    session.reported_status = None;
+
+    let status_message = match (&session.state, &state) {
+        (_, ConnectionState::Connected) => {
+            session.last_refusal = None;
+            Some("Connected to Archipelago".to_string())
+        },
+        (_, ConnectionState::Refused(errors)) => {
+            if session.last_refusal.as_ref() == Some(errors) {
+                None
+            } else {
+                session.last_refusal = Some(errors.clone());
+                Some(format!("Connection refused: {}", errors.join(", ")))
+            }
+        },
+        (ConnectionState::Connected, _) => Some("Disconnected from Archipelago".to_string()),
+        _ => None
+    };
+    session.state = state;
+
+    if let Some(status_message) = status_message {
+        queue_message_popup(vec![status_message]);
+    }
+}
+
+// Ready once a randomizer save is loaded, Playing once the player leaves the room it loaded in, and back to Connected on the title screen.
+// Goal replaces all of them once reached, and is reported again after a reconnect like any other status
+pub fn update_client_status(memory: &dyn GameMemory) {
+    let Ok(mut session) = SESSION.try_lock() else { return };
+    if goal::goal_reached() {
+        session.client_status = ClientStatus::ClientGoal;
+        return;
+    }
+    if !randomizer_save_loaded(memory) {
+        session.start_room = None;
+        session.client_status = ClientStatus::ClientConnected;
+        return;
+    }
+
+    let room = memory.current_room();
+    let start_room = *session.start_room.get_or_insert(room);
+    if session.client_status != ClientStatus::ClientPlaying && room != start_room {
+        session.client_status = ClientStatus::ClientPlaying;
+    } else if session.client_status == ClientStatus::ClientConnected {
+        session.client_status = ClientStatus::ClientReady;
+    }
+}
+
+// Each change is reported once, except the goal, which is repeated until the server announces it
+pub fn take_status_update() -> Option<ClientStatus> {
+    let mut session = SESSION.lock().unwrap();
+    if !status_due(&session) {
+        return None;
+    }
+    if session.client_status == ClientStatus::ClientGoal {
+        goal::record_notice();
+    }
+    info!("Reporting client status {:?}", session.client_status);
+    session.reported_status = Some(session.client_status);
+    Some(session.client_status)
+}
+
+pub fn status_update_pending() -> bool {
+    status_due(&SESSION.lock().unwrap())
+}
+
+// The status is reported again once the connection is back
+pub fn status_update_failed() {
+    SESSION.lock().unwrap().reported_status = None;
+    goal::notice_failed();
+}
+
+fn status_due(session: &Session) -> bool {
+    let goal_notice_due = session.client_status == ClientStatus::ClientGoal && goal::notice_due();
+    session.state == ConnectionState::Connected && (session.reported_status != Some(session.client_status) || goal_notice_due)
+}
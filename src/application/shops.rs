@@ -0,0 +1,63 @@
+use log::debug;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+use crate::ShopScout;
+use crate::application::game_memory::GameMemory;
+
+// Set by a flag entry the launcher adds to the start of every shop card, holding that shop's index from the config
+const SHOP_OPENED_FLAG: usize = 0x869;
+
+static SCOUTED_SHOPS: LazyLock<Mutex<HashSet<u8>>> = LazyLock::new(|| { Mutex::new(HashSet::new()) });
+static PENDING_SCOUTS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShopScouting {
+    Off,
+    Scout,
+    Hint
+}
+
+impl ShopScouting {
+    pub fn from_config(shop_scouting: &str) -> ShopScouting {
+        match shop_scouting {
+            "scout" => ShopScouting::Scout,
+            "hint" => ShopScouting::Hint,
+            _ => ShopScouting::Off
+        }
+    }
+
+    // LocationScouts create_as_hint value, 2 only announces hints that didn't already exist
+    pub fn create_as_hint(&self) -> i64 {
+        match self {
+            ShopScouting::Hint => 2,
+            _ => 0
+        }
+    }
+}
+
+// Each shop is only scouted once per session, since its contents never change
+pub fn detect_opened_shop(memory: &dyn GameMemory, shop_scouts: &[ShopScout]) {
+    let shop = memory.global_flag(SHOP_OPENED_FLAG);
+    if shop == 0 {
+        return;
+    }
+    memory.set_global_flag(SHOP_OPENED_FLAG, 0);
+
+    if !SCOUTED_SHOPS.lock().unwrap().insert(shop) {
+        return;
+    }
+    if let Some(shop_scout) = shop_scouts.iter().find(|shop_scout| shop_scout.shop == shop) {
+        debug!("Shop {} opened, scouting locations {:?}", shop, shop_scout.location_ids);
+        PENDING_SCOUTS.lock().unwrap().extend(shop_scout.location_ids.iter());
+    }
+}
+
+pub fn take_pending_scouts() -> Vec<i64> {
+    PENDING_SCOUTS.try_lock().map(|mut pending_scouts| std::mem::take(&mut *pending_scouts)).unwrap_or_default()
+}
+
+// Puts scouts back if sending them failed, so they go out after reconnecting
+pub fn requeue_scouts(locations: Vec<i64>) {
+    PENDING_SCOUTS.lock().unwrap().extend(locations);
+}
@@ -0,0 +1,265 @@
+use log::{debug, info, warn};
+use pelite::FileMap;
+use pelite::pattern;
+use pelite::pe32::{Pe, PeFile};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use game_versions::{GameVersion, load_versions, missing_symbols};
+
+const SIGNATURES_FILENAME: &str = "lamulana-signatures.toml";
+const EMBEDDED_SIGNATURES: &str = include_str!("../../signatures.toml");
+const EXECUTABLE_FILENAME: &str = "LaMulanaWin.exe";
+
+// A named byte pattern in pelite syntax. The symbol resolves to the first `'` save in the pattern,
+// or the start of the match if there is none, so data symbols can be reached through the code that references them (e.g. `A1 *'`)
+#[derive(Deserialize, Clone)]
+pub struct Signature {
+    pub name: String,
+    pub pattern: String
+}
+
+#[derive(Deserialize)]
+struct SignatureFile {
+    signature: Vec<Signature>
+}
+
+// Prefers a signatures file next to the executable, the same way as the versions file
+pub fn read_signatures() -> Result<Vec<Signature>, String> {
+    let file_contents = fs::read_to_string(SIGNATURES_FILENAME).unwrap_or(EMBEDDED_SIGNATURES.to_string());
+    parse_signatures(&file_contents)
+}
+
+fn parse_signatures(file_contents: &str) -> Result<Vec<Signature>, String> {
+    let signature_file = toml::from_str::<SignatureFile>(file_contents).map_err(|e| format!("Error parsing signatures: {}", e))?;
+    Ok(signature_file.signature)
+}
+
+pub fn scan_file(file_path: &str, signatures: &[Signature]) -> Result<HashMap<String, usize>, String> {
+    let map = FileMap::open(file_path).map_err(|e| format!("Error opening {}: {}", file_path, e))?;
+    let file = PeFile::from_bytes(&map).map_err(|e| format!("Error parsing {}: {}", file_path, e))?;
+    Ok(scan(file, signatures))
+}
+
+// Resolves each signature to its virtual address at the preferred image base, matching the symbols in versions.toml.
+// Signatures without a pattern, or that fail to parse, don't match, or match more than once are left out of the result
+pub fn scan(file: PeFile, signatures: &[Signature]) -> HashMap<String, usize> {
+    let image_base = file.optional_header().ImageBase as usize;
+    let scanner = file.scanner();
+
+    signatures.iter().filter_map(|signature| {
+        if signature.pattern.trim().is_empty() {
+            debug!("Signature {} has no pattern", signature.name);
+            return None;
+        }
+
+        let parsed_pattern = pattern::parse(&signature.pattern).map_err(|e| {
+            warn!("Invalid pattern for signature {}: {}", signature.name, e);
+        }).ok()?;
+
+        let mut save = [0u32; 2];
+        let mut matches = scanner.matches_code(&parsed_pattern);
+        if !matches.next(&mut save) {
+            warn!("Signature {} not found", signature.name);
+            return None;
+        }
+        if matches.next(&mut [0u32; 2]) {
+            warn!("Signature {} matched more than once", signature.name);
+            return None;
+        }
+
+        let rva = if save[1] != 0 { save[1] } else { save[0] };
+        Some((signature.name.clone(), image_base + rva as usize))
+    }).collect::<HashMap<_,_>>()
+}
+
+// Known versions always use the symbols from the versions file, with the scan only used as a cross check.
+// Unknown versions fall back to the scan, provided every required symbol was found
+pub fn resolve_game_version(app_version: &str) -> Result<GameVersion, String> {
+    let known_version = load_versions()?.into_iter().find(|game_version| game_version.version == app_version);
+
+    // No patterns have been taken from the supported executables yet, so the shipped file doesn't resolve anything
+    let scanned_offsets = match read_signatures() {
+        Ok(signatures) if signatures.iter().all(|signature| signature.pattern.trim().is_empty()) => {
+            info!("Skipping signature scan: no signature has a pattern");
+            None
+        },
+        Ok(signatures) => scan_file(EXECUTABLE_FILENAME, &signatures).map_err(|e| {
+            warn!("{}", e);
+        }).ok(),
+        Err(e) => {
+            warn!("Skipping signature scan: {}", e);
+            None
+        }
+    };
+
+    match (known_version, scanned_offsets) {
+        (Some(known_version), Some(scanned_offsets)) => {
+            cross_check(&known_version.symbols, &scanned_offsets);
+            Ok(known_version)
+        },
+        (Some(known_version), None) => Ok(known_version),
+        (None, Some(scanned_offsets)) => {
+            let scanned_version = GameVersion {
+                version: app_version.to_string(),
+                symbols: scanned_offsets,
+                ..Default::default()
+            };
+            let missing_symbols = missing_symbols(&scanned_version);
+            if missing_symbols.is_empty() {
+                info!("Resolved all symbols for unknown version {} by signature scan", app_version);
+                Ok(scanned_version)
+            } else {
+                Err(format!("Unsupported version {}. Signature scan could not resolve: {}", app_version, missing_symbols.join(", ")))
+            }
+        },
+        (None, None) => Err(format!("Unsupported version {}.", app_version))
+    }
+}
+
+fn cross_check(known_offsets: &HashMap<String, usize>, scanned_offsets: &HashMap<String, usize>) {
+    for (name, scanned_address) in scanned_offsets {
+        match known_offsets.get(name) {
+            Some(known_address) if known_address == scanned_address => {
+                debug!("Signature {} matches known address {:#010x}", name, known_address);
+            },
+            Some(known_address) => {
+                warn!("Signature {} resolved to {:#010x}, expected {:#010x}", name, scanned_address, known_address);
+            },
+            None => {
+                warn!("Signature {} has no known address to check against", name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_versions::REQUIRED_SYMBOLS;
+
+    const IMAGE_BASE: u32 = 0x400000;
+    const CODE_RVA: u32 = 0x1000;
+    const FILE_ALIGNMENT: usize = 0x200;
+
+    // push ebp; mov ebp, esp; sub esp, 0x10 / mov eax, [0x401100]; ret / push esi; push edi twice
+    const FIXTURE_CODE: &[u8] = &[
+        0x55, 0x8b, 0xec, 0x83, 0xec, 0x10,
+        0xa1, 0x00, 0x11, 0x40, 0x00, 0xc3,
+        0x56, 0x57, 0xc3,
+        0x56, 0x57, 0xc3
+    ];
+
+    // A PE32 image with a single code section at CODE_RVA holding `code`
+    fn fixture_pe(code: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; FILE_ALIGNMENT * 2];
+        image[0..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let mut file_header = Vec::new();
+        file_header.extend(0x14cu16.to_le_bytes());
+        file_header.extend(1u16.to_le_bytes());
+        file_header.extend([0u8; 12]);
+        file_header.extend(0xe0u16.to_le_bytes());
+        file_header.extend(0x102u16.to_le_bytes());
+        image[0x44..0x58].copy_from_slice(&file_header);
+
+        let mut optional_header = Vec::new();
+        optional_header.extend(0x10bu16.to_le_bytes());
+        optional_header.extend([0u8; 2]);
+        for value in [FILE_ALIGNMENT as u32, 0, 0, CODE_RVA, CODE_RVA, CODE_RVA, IMAGE_BASE, 0x1000, FILE_ALIGNMENT as u32] {
+            optional_header.extend(value.to_le_bytes());
+        }
+        for value in [4u16, 0, 0, 0, 4, 0] {
+            optional_header.extend(value.to_le_bytes());
+        }
+        for value in [0u32, 0x2000, FILE_ALIGNMENT as u32, 0] {
+            optional_header.extend(value.to_le_bytes());
+        }
+        optional_header.extend(2u16.to_le_bytes());
+        optional_header.extend(0u16.to_le_bytes());
+        for value in [0x100000u32, 0x1000, 0x100000, 0x1000, 0, 16] {
+            optional_header.extend(value.to_le_bytes());
+        }
+        optional_header.resize(0xe0, 0);
+        image[0x58..0x138].copy_from_slice(&optional_header);
+
+        let mut section_header = Vec::new();
+        section_header.extend(b".text\0\0\0");
+        for value in [FILE_ALIGNMENT as u32, CODE_RVA, FILE_ALIGNMENT as u32, FILE_ALIGNMENT as u32, 0, 0, 0, 0x60000020] {
+            section_header.extend(value.to_le_bytes());
+        }
+        section_header.truncate(0x28);
+        image[0x138..0x160].copy_from_slice(&section_header);
+
+        image[FILE_ALIGNMENT..FILE_ALIGNMENT + code.len()].copy_from_slice(code);
+        image
+    }
+
+    fn signature(name: &str, pattern: &str) -> Signature {
+        Signature { name: name.to_string(), pattern: pattern.to_string() }
+    }
+
+    fn scan_fixture(signatures: &[Signature]) -> HashMap<String, usize> {
+        let image = fixture_pe(FIXTURE_CODE);
+        scan(PeFile::from_bytes(&image).unwrap(), signatures)
+    }
+
+    #[test]
+    fn code_signatures_resolve_to_the_start_of_the_match() {
+        let scanned_offsets = scan_fixture(&[signature("game_loop", "55 8B EC 83 EC 10")]);
+        assert_eq!(scanned_offsets, HashMap::from([("game_loop".to_string(), 0x401000)]));
+    }
+
+    #[test]
+    fn data_signatures_resolve_through_the_referencing_code() {
+        let scanned_offsets = scan_fixture(&[signature("global_flags", "A1 *'"), signature("se", "83 EC 10 ' A1")]);
+        assert_eq!(scanned_offsets, HashMap::from([("global_flags".to_string(), 0x401100), ("se".to_string(), 0x401006)]));
+    }
+
+    #[test]
+    fn missing_signatures_are_left_out() {
+        assert!(scan_fixture(&[signature("game_loop", "DE AD BE EF")]).is_empty());
+    }
+
+    #[test]
+    fn ambiguous_signatures_are_left_out() {
+        assert!(scan_fixture(&[signature("game_loop", "56 57 C3")]).is_empty());
+    }
+
+    #[test]
+    fn empty_and_invalid_patterns_are_left_out() {
+        assert!(scan_fixture(&[signature("game_loop", ""), signature("se", "ZZ")]).is_empty());
+    }
+
+    #[test]
+    fn embedded_signatures_name_every_required_symbol_once() {
+        let signatures = parse_signatures(EMBEDDED_SIGNATURES).unwrap();
+        let mut names = signatures.iter().map(|signature| signature.name.as_str()).collect::<Vec<&str>>();
+        names.sort();
+        let mut required_symbols = REQUIRED_SYMBOLS.to_vec();
+        required_symbols.sort();
+        assert_eq!(names, required_symbols);
+
+        for signature in signatures.iter().filter(|signature| !signature.pattern.is_empty()) {
+            assert!(pattern::parse(&signature.pattern).is_ok(), "Invalid pattern for signature {}", signature.name);
+        }
+    }
+
+    // Needs a real executable, run with --ignored and LAMULANA_EXE set to its path
+    #[test]
+    #[ignore = "needs LAMULANA_EXE to point at a supported LaMulanaWin.exe"]
+    fn embedded_signatures_resolve_to_the_known_addresses() {
+        let executable_path = std::env::var("LAMULANA_EXE").unwrap();
+        let scanned_offsets = scan_file(&executable_path, &parse_signatures(EMBEDDED_SIGNATURES).unwrap()).unwrap();
+        let known_version = load_versions().unwrap().into_iter()
+            .find(|game_version| REQUIRED_SYMBOLS.iter().any(|symbol| game_version.symbols.get(*symbol) == scanned_offsets.get(*symbol)))
+            .expect("No signature resolved to a known address");
+
+        for symbol in REQUIRED_SYMBOLS {
+            assert_eq!(scanned_offsets.get(symbol), known_version.symbols.get(symbol), "Signature {} for version {}", symbol, known_version.version);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use log::trace;
+use std::sync::Mutex;
+
+use crate::application::Application;
+use crate::lm_structs::script_header::{ScriptHeader, ScriptSubHeader};
+use crate::lm_structs::taskdata::TaskData;
+use crate::screenplay;
+
+pub static TEXT_ARENA: Mutex<TextArena> = Mutex::new(TextArena::new());
+
+// A single script line that has been pointed at text owned by the arena.
+// The buffer is boxed so its address never moves while the game may still be reading it.
+struct TextOverwrite {
+    card: usize,
+    line: usize,
+    original: ScriptSubHeader,
+    owner_uid_address: usize,
+    owner_uid: u32,
+    encoded: Box<[u16]>
+}
+
+pub struct TextArena {
+    overwrites: Vec<TextOverwrite>
+}
+
+impl TextArena {
+    pub const fn new() -> TextArena {
+        TextArena { overwrites: Vec::new() }
+    }
+
+    // Point a script line at the encoded text for as long as the owning task is alive.
+    // Overwriting a line that is already held keeps the original game text, so it can still be restored.
+    pub fn overwrite(&mut self, application: &Application, owner: &TaskData, card: usize, line: usize, encoded: Vec<u16>) {
+        let script_line = script_line(application, card, line);
+
+        let original = match self.overwrites.iter().position(|overwrite| overwrite.card == card && overwrite.line == line) {
+            Some(index) => self.overwrites.remove(index).original,
+            None => *script_line
+        };
+
+        let encoded = encoded.into_boxed_slice();
+        *script_line = ScriptSubHeader {
+            pointer: encoded.as_ptr() as usize,
+            data_num: encoded.len() as i32,
+            font_num: screenplay::font_count(&encoded)
+        };
+
+        trace!("Overwrote script card {} line {} for task {}", card, line, owner.id.uid);
+        self.overwrites.push(TextOverwrite {
+            card,
+            line,
+            original,
+            owner_uid_address: &owner.id.uid as *const u32 as usize,
+            owner_uid: owner.id.uid,
+            encoded
+        });
+    }
+
+    // Restore the original text of every line whose owning task has since ended, then free its buffer.
+    pub fn release_finished(&mut self, application: &Application) {
+        self.overwrites.retain(|overwrite| {
+            if overwrite.owner_uid == *application.read_raw_address::<u32>(overwrite.owner_uid_address) {
+                return true;
+            }
+
+            let script_line = script_line(application, overwrite.card, overwrite.line);
+            // Only restore if the line still points at our buffer, the game may have reloaded its scripts since
+            if script_line.pointer == overwrite.encoded.as_ptr() as usize {
+                *script_line = overwrite.original;
+            }
+            trace!("Restored script card {} line {}", overwrite.card, overwrite.line);
+            false
+        });
+    }
+}
+
+fn script_line(application: &Application, card: usize, line: usize) -> &mut ScriptSubHeader {
+    let script_header: &*const ScriptHeader = application.read_address("script_header_pointer");
+    let line_header = unsafe { (*script_header.add(card)).data as *mut ScriptSubHeader };
+    unsafe { &mut *line_header.add(line) }
+}
@@ -3,6 +3,7 @@
 #![feature(unboxed_closures)]
 
 use archipelago_api::api::APError;
+use game_versions::DEFAULT_MESSAGE_FEED_TYPES;
 use log::{warn, LevelFilter};
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Root};
@@ -21,6 +22,8 @@ use winapi::um::libloaderapi::GetModuleHandleW;
 use winapi::um::processthreadsapi::ExitProcess;
 
 use crate::application::Application;
+use crate::application::guard::install_panic_hook;
+use crate::application::signatures::resolve_game_version;
 
 pub mod application;
 pub mod lm_structs;
@@ -28,6 +31,9 @@ pub mod screenplay;
 pub mod utils;
 
 const CONFIG_FILENAME: &str = "lamulana-config.toml";
+const SETTINGS_FILENAME: &str = "lamulana-settings.toml";
+// Only these keys are taken from the settings file, everything else is specific to the seed
+const SETTINGS_KEYS: [&str; 4] = ["item_summary_threshold", "message_feed_types", "hint_hotkey", "shop_scouting"];
 
 pub static APPLICATION: LazyLock<Application> = LazyLock::new(|| { init_app() });
 
@@ -45,6 +51,37 @@ pub struct ArchipelagoItem {
     pub obtain_value: u8
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShopScout {
+    pub shop: u8,
+    pub location_ids: Vec<i64>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FillerItem {
+    pub ap_id: i64,
+    pub item_name: String,
+    // Shown on the popup, the inventory slot only says where the quantity is kept
+    pub item_id: u32,
+    pub inventory_slot: usize,
+    pub quantity: u16,
+    pub max_quantity: u16
+}
+
+// Tiers are AP item ids in the order they are handed out
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProgressiveItem {
+    pub ap_id: i64,
+    pub item_name: String,
+    pub tiers: Vec<i64>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CoinChest {
+    pub location_id: i64,
+    pub quantity: i16
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub server_url: String,
@@ -52,15 +89,75 @@ pub struct AppConfig {
     pub log_file_name: String,
     pub local_player_id: i64,
     pub log_level: String,
+    #[serde(default = "default_item_summary_threshold")]
+    pub item_summary_threshold: usize,
+    #[serde(default = "default_message_feed_types")]
+    pub message_feed_types: Vec<String>,
+    #[serde(default = "default_hint_hotkey")]
+    pub hint_hotkey: String,
+    #[serde(default)]
+    pub hint_items: Vec<i64>,
+    #[serde(default = "default_shop_scouting")]
+    pub shop_scouting: String,
+    #[serde(default = "default_goal")]
+    pub goal: String,
+    #[serde(default = "default_goal_guardians")]
+    pub goal_guardians: u8,
+    #[serde(default)]
+    pub hell_temple_flag: Option<u16>,
+    #[serde(default)]
+    pub shop_scouts: Vec<ShopScout>,
+    #[serde(default)]
+    pub filler_items: Vec<FillerItem>,
+    #[serde(default)]
+    pub coin_chests: Vec<CoinChest>,
+    #[serde(default)]
+    pub progressive_items: Vec<ProgressiveItem>,
     pub players: Vec<ArchipelagoPlayer>,
     pub item_mapping: Vec<ArchipelagoItem>,
 }
 
+fn default_item_summary_threshold() -> usize {
+    5
+}
+
+fn default_hint_hotkey() -> String {
+    "F9".to_string()
+}
+
+fn default_shop_scouting() -> String {
+    "hint".to_string()
+}
+
+fn default_goal() -> String {
+    "escape".to_string()
+}
+
+fn default_goal_guardians() -> u8 {
+    8
+}
+
+fn default_message_feed_types() -> Vec<String> {
+    DEFAULT_MESSAGE_FEED_TYPES.iter().map(|message_type| message_type.to_string()).collect()
+}
+
 impl AppConfig {
     fn players_lookup(&self) -> HashMap<i64, String> {
         self.players.clone().into_iter().map(|player| (player.id, player.name)).collect::<HashMap<_,_>>()
     }
 
+    fn coin_chests(&self) -> HashMap<i64, i16> {
+        self.coin_chests.iter().map(|coin_chest| (coin_chest.location_id, coin_chest.quantity)).collect::<HashMap<_,_>>()
+    }
+
+    fn fillers(&self) -> HashMap<i64, FillerItem> {
+        self.filler_items.clone().into_iter().map(|filler_item| (filler_item.ap_id, filler_item)).collect::<HashMap<_,_>>()
+    }
+
+    fn progressives(&self) -> HashMap<i64, ProgressiveItem> {
+        self.progressive_items.clone().into_iter().map(|progressive_item| (progressive_item.ap_id, progressive_item)).collect::<HashMap<_,_>>()
+    }
+
     fn items(&self) -> HashMap<u16, ArchipelagoItem> {
         self.item_mapping.clone().into_iter().map(|mapping| (mapping.flag, mapping)).collect::<HashMap<_,_>>()
     }
@@ -77,10 +174,33 @@ extern "system" fn DllMain(_h_inst_dll: HINSTANCE, fdw_reason: DWORD, _lpv_reser
 
 fn read_config() -> Result<AppConfig, String> {
     let file_contents = fs::read_to_string(CONFIG_FILENAME).map_err(|e| format!("Error reading config: {}", e.to_string()))?;
-    let app_config = toml::from_str::<AppConfig>(&file_contents).map_err(|e| format!("Error parsing config: {}", e.to_string()))?;
+    let mut config_value = toml::from_str::<toml::Value>(&file_contents).map_err(|e| format!("Error parsing config: {}", e.to_string()))?;
+    apply_settings(&mut config_value)?;
+    let app_config = config_value.try_into::<AppConfig>().map_err(|e| format!("Error parsing config: {}", e.to_string()))?;
     Ok(app_config)
 }
 
+// The settings file is optional, the seed's config already carries defaults for every key
+fn apply_settings(config_value: &mut toml::Value) -> Result<(), String> {
+    let Ok(settings_contents) = fs::read_to_string(SETTINGS_FILENAME) else {
+        return Ok(());
+    };
+    let settings_value = toml::from_str::<toml::Value>(&settings_contents).map_err(|e| format!("Error parsing settings: {}", e))?;
+    merge_settings(config_value, &settings_value);
+    Ok(())
+}
+
+fn merge_settings(config_value: &mut toml::Value, settings_value: &toml::Value) {
+    let (Some(config_table), Some(settings_table)) = (config_value.as_table_mut(), settings_value.as_table()) else {
+        return;
+    };
+    for key in SETTINGS_KEYS {
+        if let Some(setting) = settings_table.get(key) {
+            config_table.insert(key.to_string(), setting.clone());
+        }
+    }
+}
+
 fn init_logger(app_config: &AppConfig) {
     let level_filter = match app_config.log_level.as_str() {
         "OFF" => LevelFilter::Off,
@@ -109,11 +229,13 @@ fn init_app() -> Application {
         unsafe{ ExitProcess(1) };
     }).unwrap();
     init_logger(&app_config);
+    install_panic_hook();
 
     let randomizer = Mutex::new(Err(APError::NoConnection));
     let app_version = get_application_version();
+    let game_version = resolve_game_version(&app_version);
 
-    Application { address, randomizer, app_config, app_version}
+    Application { address, randomizer, app_config, app_version, game_version }
 }
 
 fn get_application() -> &'static Application {
@@ -137,3 +259,19 @@ fn get_application_version() -> String {
         panic!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_override_only_their_own_keys() {
+        let mut config_value = toml::from_str::<toml::Value>("server_url = \"localhost\"\nhint_hotkey = \"F9\"\nitem_summary_threshold = 5").unwrap();
+        let settings_value = toml::from_str::<toml::Value>("hint_hotkey = \"F10\"\nserver_url = \"elsewhere\"").unwrap();
+        merge_settings(&mut config_value, &settings_value);
+
+        assert_eq!(config_value["hint_hotkey"].as_str(), Some("F10"));
+        assert_eq!(config_value["server_url"].as_str(), Some("localhost"));
+        assert_eq!(config_value["item_summary_threshold"].as_integer(), Some(5));
+    }
+}
@@ -32,3 +32,16 @@ pub fn encode(word: String) -> Vec<u16> {
         })
         .collect::<Vec<u16>>()
 }
+
+pub fn encode_lines(lines: Vec<String>) -> Vec<u16> {
+    let mut encoded = lines.into_iter()
+        .map(|line| encode(line))
+        .collect::<Vec<Vec<u16>>>()
+        .join(&0x0045);
+    encoded.push(0x000a);
+    encoded
+}
+
+pub fn font_count(encoded: &[u16]) -> i32 {
+    encoded.iter().filter(|letter| **letter >= 0x100).count() as i32
+}